@@ -25,6 +25,6 @@ fn setup_initial_window_ui(
         return;
     };
     if let Some(panel) = initial_panel.0 {
-        window.panels.push(panel);
+        window.insert(panel);
     }
 }
@@ -13,6 +13,11 @@ pub struct DioxusPlugin {
     /// max fps that dioxus uis should be rendered at.
     pub dioxus_render_fps_cap: u32,
     pub main_window_ui: Option<fn() -> Element>,
+    /// Root component every ui quad's document is built from, unless the quad has its
+    /// own `DioxusRoot`. Must render `PanelsOutlet` for panels to appear.
+    pub root_ui: Option<fn() -> Element>,
+    /// Connect to the dioxus devserver for hot reloading. Turn off for release builds.
+    pub devtools: bool,
 }
 
 impl Plugin for DioxusPlugin {
@@ -29,6 +34,8 @@ impl Plugin for DioxusPlugin {
         app.add_plugins(dioxus_signals_mirror_plugin);
         app.add_plugins(DioxusRenderPlugin {
             fps_cap: self.dioxus_render_fps_cap,
+            default_root: self.root_ui,
+            devtools: self.devtools,
         });
     }
 }
@@ -0,0 +1,149 @@
+use bevy_dioxus_render::focus::FocusNavigation;
+use bevy_dioxus_render::worker::{VdomCommand, VdomThreadRegistry};
+use bevy_dioxus_render::{DioxusUiPickFilter, DioxusUiPickState, DioxusWindowUiQuad};
+use bevy_ecs::prelude::*;
+use bevy_input::gamepad::{Gamepad, GamepadButton};
+use bevy_time::{Real, Time};
+use blitz_traits::events::{BlitzKeyEvent, KeyState, UiEvent};
+use dioxus_html::*;
+
+use crate::keyboard::DioxusFocusedDocument;
+use crate::mouse::WorldSpacePickingState;
+
+/// Stick deflection that counts as pushing a direction.
+const STICK_THRESHOLD: f32 = 0.5;
+/// Seconds a direction is held before focus starts repeating.
+const REPEAT_DELAY: f32 = 0.4;
+/// Seconds between repeats while a direction is held.
+const REPEAT_INTERVAL: f32 = 0.12;
+
+/// Gamepad navigation of documents, off by default since it takes the D-pad, left stick,
+/// A and B.
+///
+/// With no target, gamepads navigate the focused document, then the document under the
+/// pointer, and the window ui otherwise. Set a target to navigate a world-space document
+/// without a mouse.
+#[derive(Resource, Default)]
+pub struct DioxusGamepadNavigation {
+    pub enabled: bool,
+    pub target: Option<Entity>,
+}
+
+/// The direction held last frame, and when it next repeats.
+#[derive(Default)]
+pub(crate) struct HeldDirection {
+    direction: Option<FocusNavigation>,
+    next_repeat: f32,
+}
+
+/// The direction pushed on the D-pad or left stick of any gamepad.
+fn held_direction(gamepads: &Query<&Gamepad>) -> Option<FocusNavigation> {
+    for gamepad in gamepads {
+        let stick = gamepad.left_stick();
+        let direction = if gamepad.pressed(GamepadButton::DPadUp) {
+            Some(FocusNavigation::Up)
+        } else if gamepad.pressed(GamepadButton::DPadDown) {
+            Some(FocusNavigation::Down)
+        } else if gamepad.pressed(GamepadButton::DPadLeft) {
+            Some(FocusNavigation::Left)
+        } else if gamepad.pressed(GamepadButton::DPadRight) {
+            Some(FocusNavigation::Right)
+        } else if stick.length() < STICK_THRESHOLD {
+            None
+        } else if stick.x.abs() > stick.y.abs() {
+            Some(if stick.x > 0.0 {
+                FocusNavigation::Right
+            } else {
+                FocusNavigation::Left
+            })
+        } else {
+            // Stick y points up
+            Some(if stick.y > 0.0 {
+                FocusNavigation::Up
+            } else {
+                FocusNavigation::Down
+            })
+        };
+        if direction.is_some() {
+            return direction;
+        }
+    }
+    None
+}
+
+/// Moves focus spatially with the D-pad and left stick, clicks the focused element with
+/// A/Cross, and sends Escape with B/Circle.
+pub(crate) fn gamepad_focus_navigation(
+    registry: NonSendMut<VdomThreadRegistry>,
+    gamepads: Query<&Gamepad>,
+    navigation: Res<DioxusGamepadNavigation>,
+    focused: Res<DioxusFocusedDocument>,
+    pick_state: Res<DioxusUiPickState>,
+    picking_state: Res<WorldSpacePickingState>,
+    window_ui: Query<Entity, With<DioxusWindowUiQuad>>,
+    time: Res<Time<Real>>,
+    mut held: Local<HeldDirection>,
+) {
+    if !navigation.enabled {
+        *held = HeldDirection::default();
+        return;
+    }
+    let target = navigation
+        .target
+        .or(focused.entity)
+        .or_else(|| match pick_state.active {
+            DioxusUiPickFilter::WORLD_SPACE => {
+                picking_state.pick.as_ref().map(|pick| pick.hit_entity)
+            }
+            _ => window_ui.iter().next(),
+        });
+    let Some((target, worker)) =
+        target.and_then(|target| Some((target, registry.worker_for(target)?)))
+    else {
+        return;
+    };
+
+    let now = time.elapsed_secs();
+    let direction = held_direction(&gamepads);
+    let mut steps = Vec::new();
+    if direction != held.direction {
+        held.direction = direction;
+        held.next_repeat = now + REPEAT_DELAY;
+        steps.extend(direction);
+    } else if direction.is_some() && now >= held.next_repeat {
+        held.next_repeat = now + REPEAT_INTERVAL;
+        steps.extend(direction);
+    }
+    if gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::South))
+    {
+        steps.push(FocusNavigation::Activate);
+    }
+    for step in steps {
+        let _ = worker.cmd_tx.try_send(VdomCommand::Navigate(step));
+    }
+
+    if gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::East))
+    {
+        for state in [KeyState::Pressed, KeyState::Released] {
+            let key_event = BlitzKeyEvent {
+                key: Key::Escape,
+                code: Code::Escape,
+                modifiers: Modifiers::empty(),
+                location: Location::Standard,
+                is_auto_repeating: false,
+                is_composing: false,
+                state,
+                text: None,
+            };
+            let ui_event = match state {
+                KeyState::Pressed => UiEvent::KeyDown(key_event),
+                KeyState::Released => UiEvent::KeyUp(key_event),
+            };
+            let _ = worker.input_tx.try_send((target, ui_event));
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use bevy_dioxus_render::DioxusWindowUiQuad;
+use bevy_dioxus_render::worker::VdomThreadRegistry;
+use bevy_ecs::prelude::*;
+use bevy_math::prelude::*;
+use bevy_window::{Ime, PrimaryWindow, Window};
+use blitz_traits::events::{BlitzImeEvent, UiEvent};
+
+use crate::keyboard::DioxusFocusedDocument;
+use crate::mouse::MouseState;
+
+/// Whether the player is composing text with an input method.
+#[derive(Resource, Default, Debug)]
+pub struct DioxusImeState {
+    pub composing: bool,
+}
+
+/// Forwards input method composition to the focused document.
+pub(crate) fn handle_ime_messages(
+    registry: NonSendMut<VdomThreadRegistry>,
+    mut ime_events: MessageReader<Ime>,
+    mut ime_state: ResMut<DioxusImeState>,
+    focused: Res<DioxusFocusedDocument>,
+) {
+    for event in ime_events.read() {
+        let ime_event = match event {
+            Ime::Preedit { value, cursor, .. } => {
+                ime_state.composing = !value.is_empty();
+                BlitzImeEvent::Preedit(value.clone(), *cursor)
+            }
+            Ime::Commit { value, .. } => {
+                ime_state.composing = false;
+                BlitzImeEvent::Commit(value.clone())
+            }
+            Ime::Enabled { .. } => BlitzImeEvent::Enabled,
+            Ime::Disabled { .. } => {
+                ime_state.composing = false;
+                BlitzImeEvent::Disabled
+            }
+        };
+
+        if let Some(entity) = focused.entity
+            && let Some(worker) = registry.worker_for(entity)
+        {
+            let _ = worker.input_tx.try_send((entity, UiEvent::Ime(ime_event)));
+        }
+    }
+}
+
+/// Enables the window's input method while a text input is focused, placing the candidate
+/// box under it. World-space documents place it where the cursor was when the input was
+/// focused, usually where the player clicked it. Only disables an input method it enabled,
+/// so one the app enabled for its own text fields stays on.
+pub(crate) fn sync_ime_window(
+    registry: NonSend<VdomThreadRegistry>,
+    focused: Res<DioxusFocusedDocument>,
+    mouse_state: Res<MouseState>,
+    window_ui: Query<(), With<DioxusWindowUiQuad>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut enabled_here: Local<bool>,
+) {
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+    let text_input = focused.entity.and_then(|entity| {
+        let rect = registry.worker_for(entity)?.focused_text_input?;
+        Some((entity, rect))
+    });
+
+    let enabled = text_input.is_some();
+    let just_enabled = enabled && !*enabled_here;
+    if enabled != *enabled_here {
+        *enabled_here = enabled;
+        window.ime_enabled = enabled;
+    }
+    let Some((entity, rect)) = text_input else {
+        return;
+    };
+    let position = if window_ui.contains(entity) {
+        Vec2::new(rect.min.x, rect.max.y)
+    } else if just_enabled {
+        Vec2::new(mouse_state.x, mouse_state.y)
+    } else {
+        return;
+    };
+    if window.ime_position != position {
+        window.ime_position = position;
+    }
+}
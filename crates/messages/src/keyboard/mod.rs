@@ -1,5 +1,4 @@
-use bevy_dioxus_render::worker::VdomThreadRegistry;
-use bevy_dioxus_render::{DioxusUiPickFilter, DioxusUiPickState, DioxusWindowUiQuad};
+use bevy_dioxus_render::worker::{VdomCommand, VdomThreadRegistry};
 use bevy_ecs::prelude::*;
 use bevy_input::{
     ButtonState,
@@ -8,26 +7,84 @@ use bevy_input::{
 use blitz_traits::events::{BlitzKeyEvent, KeyState, UiEvent};
 use dioxus_html::*;
 
-use super::mouse::{MouseState, WorldSpacePickingState};
+use super::ime::DioxusImeState;
+use super::mouse::MouseState;
+
+/// The ui quad whose document receives keyboard input.
+///
+/// Pressing a pointer on a document focuses it, and pressing outside every document blurs
+/// it. Set it to focus a document programmatically. A document stays focused after a click
+/// on any of its buttons, so to ignore game hotkeys only while the player types, read
+/// `text_input` or use [`no_dioxus_text_input_focused`] as a run condition.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DioxusFocusedDocument {
+    pub entity: Option<Entity>,
+    /// Whether a text input has focus inside the focused document. Kept up to date from
+    /// the document.
+    pub text_input: bool,
+}
+
+impl DioxusFocusedDocument {
+    pub fn focus(&mut self, entity: Entity) {
+        self.entity = Some(entity);
+    }
+
+    pub fn blur(&mut self) {
+        self.entity = None;
+        self.text_input = false;
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.entity.is_some()
+    }
+}
+
+/// Run condition for systems that should skip keyboard input while the player types in a
+/// document.
+pub fn no_dioxus_text_input_focused(focused: Res<DioxusFocusedDocument>) -> bool {
+    !focused.text_input
+}
+
+/// Blurs the previously focused document when focus moves, drops focus from documents
+/// that no longer exist, and tracks whether the focused document has a text input focused.
+pub(crate) fn sync_focused_document(
+    registry: NonSend<VdomThreadRegistry>,
+    mut focused: ResMut<DioxusFocusedDocument>,
+    mut previous: Local<Option<Entity>>,
+) {
+    if let Some(entity) = focused.entity
+        && registry.worker_for(entity).is_none()
+    {
+        focused.blur();
+    }
+    if *previous != focused.entity {
+        if let Some(worker) = previous.and_then(|entity| registry.worker_for(entity)) {
+            let _ = worker.cmd_tx.try_send(VdomCommand::Blur);
+        }
+        *previous = focused.entity;
+    }
+
+    let text_input = focused
+        .entity
+        .and_then(|entity| registry.worker_for(entity))
+        .is_some_and(|worker| worker.focused_text_input.is_some());
+    if focused.text_input != text_input {
+        focused.text_input = text_input;
+    }
+}
 
 pub(crate) fn handle_keyboard_messages(
     registry: NonSendMut<VdomThreadRegistry>,
     keyboard_input_events: ResMut<Messages<KeyboardInput>>,
     mut last_mouse_state: ResMut<MouseState>,
-    pick_state: Res<DioxusUiPickState>,
-    picking_state: Res<WorldSpacePickingState>,
-    window_ui: Query<Entity, With<DioxusWindowUiQuad>>,
+    focused: Res<DioxusFocusedDocument>,
+    ime_state: Res<DioxusImeState>,
 ) {
     if keyboard_input_events.is_empty() {
         return;
     }
 
-    // Route keys to the document the user is interacting with.
-    let target = match pick_state.active {
-        DioxusUiPickFilter::WINDOW_SPACE => window_ui.iter().next(),
-        DioxusUiPickFilter::WORLD_SPACE => picking_state.pick.as_ref().map(|pick| pick.hit_entity),
-        _ => None,
-    };
+    let target = focused.entity;
 
     for event in keyboard_input_events
         .get_cursor()
@@ -67,7 +124,7 @@ pub(crate) fn handle_keyboard_messages(
             modifiers: last_mouse_state.mods,
             location: Location::Standard,
             is_auto_repeating: event.repeat,
-            is_composing: false,
+            is_composing: ime_state.composing,
             state: key_state,
             text: event_text,
         };
@@ -78,7 +135,7 @@ pub(crate) fn handle_keyboard_messages(
         };
 
         if let Some(entity) = target {
-            if let Some(worker) = registry.workers.get(&entity) {
+            if let Some(worker) = registry.worker_for(entity) {
                 let _ = worker.input_tx.try_send((entity, ui_event));
             }
         }
@@ -599,3 +656,144 @@ fn bevy_key_code_to_blitz_code(key_code: &BevyKeyCode) -> Code {
         BevyKeyCode::F35 => Code::F35,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::{App, Update};
+    use bevy_dioxus_render::worker::VdomWorker;
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_math::Rect;
+    use crossbeam_channel::Receiver;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_non_send(VdomThreadRegistry::default())
+            .init_resource::<DioxusFocusedDocument>()
+            .add_systems(Update, sync_focused_document);
+        app
+    }
+
+    /// Register a worker with no thread behind it, returning the commands sent to it.
+    fn add_document(app: &mut App) -> (Entity, Receiver<VdomCommand>) {
+        let entity = app.world_mut().spawn_empty().id();
+        let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+        app.world_mut()
+            .run_system_once(move |mut registry: NonSendMut<VdomThreadRegistry>| {
+                let (_, result_rx) = crossbeam_channel::unbounded();
+                let (input_tx, _) = crossbeam_channel::unbounded();
+                registry.workers.insert(
+                    entity,
+                    VdomWorker {
+                        cmd_tx: cmd_tx.clone(),
+                        result_rx,
+                        input_tx,
+                        waker_flag: Default::default(),
+                        thread: None,
+                        focused_text_input: None,
+                    },
+                );
+            })
+            .unwrap();
+        (entity, cmd_rx)
+    }
+
+    fn set_text_input(app: &mut App, entity: Entity, rect: Option<Rect>) {
+        app.world_mut()
+            .run_system_once(move |mut registry: NonSendMut<VdomThreadRegistry>| {
+                registry
+                    .workers
+                    .get_mut(&entity)
+                    .unwrap()
+                    .focused_text_input = rect;
+            })
+            .unwrap();
+    }
+
+    fn focused(app: &App) -> DioxusFocusedDocument {
+        *app.world().resource::<DioxusFocusedDocument>()
+    }
+
+    #[test]
+    fn blur_clears_text_input() {
+        let mut focused = DioxusFocusedDocument::default();
+        focused.focus(Entity::PLACEHOLDER);
+        focused.text_input = true;
+        assert!(focused.is_focused());
+
+        focused.blur();
+        assert_eq!(focused, DioxusFocusedDocument::default());
+    }
+
+    #[test]
+    fn moving_focus_blurs_the_previous_document() {
+        let mut app = app();
+        let (first, first_commands) = add_document(&mut app);
+        let (second, second_commands) = add_document(&mut app);
+
+        app.world_mut()
+            .resource_mut::<DioxusFocusedDocument>()
+            .focus(first);
+        app.update();
+        assert!(first_commands.try_recv().is_err());
+
+        app.world_mut()
+            .resource_mut::<DioxusFocusedDocument>()
+            .focus(second);
+        app.update();
+        assert!(matches!(first_commands.try_recv(), Ok(VdomCommand::Blur)));
+        assert!(second_commands.try_recv().is_err());
+
+        app.world_mut()
+            .resource_mut::<DioxusFocusedDocument>()
+            .blur();
+        app.update();
+        assert!(matches!(second_commands.try_recv(), Ok(VdomCommand::Blur)));
+    }
+
+    #[test]
+    fn tracks_the_focused_documents_text_input() {
+        let mut app = app();
+        let (first, _first_commands) = add_document(&mut app);
+        let (second, _second_commands) = add_document(&mut app);
+        set_text_input(&mut app, first, Some(Rect::new(0.0, 0.0, 10.0, 10.0)));
+
+        app.world_mut()
+            .resource_mut::<DioxusFocusedDocument>()
+            .focus(first);
+        app.update();
+        assert!(focused(&app).text_input);
+
+        set_text_input(&mut app, first, None);
+        app.update();
+        assert!(!focused(&app).text_input);
+
+        set_text_input(&mut app, first, Some(Rect::new(0.0, 0.0, 10.0, 10.0)));
+        app.update();
+        app.world_mut()
+            .resource_mut::<DioxusFocusedDocument>()
+            .focus(second);
+        app.update();
+        assert!(!focused(&app).text_input);
+    }
+
+    #[test]
+    fn drops_focus_from_removed_documents() {
+        let mut app = app();
+        let (entity, _commands) = add_document(&mut app);
+        set_text_input(&mut app, entity, Some(Rect::new(0.0, 0.0, 10.0, 10.0)));
+        app.world_mut()
+            .resource_mut::<DioxusFocusedDocument>()
+            .focus(entity);
+        app.update();
+
+        app.world_mut()
+            .run_system_once(move |mut registry: NonSendMut<VdomThreadRegistry>| {
+                registry.workers.remove(&entity);
+            })
+            .unwrap();
+        app.update();
+        assert_eq!(focused(&app), DioxusFocusedDocument::default());
+    }
+}
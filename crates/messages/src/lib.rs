@@ -1,4 +1,7 @@
+pub mod gamepad;
+pub mod ime;
 pub mod keyboard;
 pub mod mouse;
 pub mod plugins;
+pub mod touch;
 pub use bevy_dioxus_tracing::*;
@@ -1,17 +1,24 @@
+use std::collections::HashMap;
+
 use bevy_dioxus_render::worker::VdomThreadRegistry;
 use bevy_dioxus_render::{DioxusUiPickFilter, DioxusUiPickState, DioxusUiQuad, DioxusWindowUiQuad};
 use bevy_dioxus_tracing::error;
 use bevy_ecs::prelude::*;
-use bevy_input::{ButtonState, mouse::MouseButtonInput, prelude::*};
+use bevy_input::mouse::{MouseButtonInput, MouseScrollUnit, MouseWheel};
+use bevy_input::{ButtonState, prelude::*};
 use bevy_math::prelude::*;
 use bevy_picking::backend::PointerHits;
+use bevy_picking::pointer::PointerId;
 use bevy_transform::components::GlobalTransform;
 use bevy_window::CursorMoved;
 use blitz_traits::events::{
-    BlitzPointerEvent, BlitzPointerId, MouseEventButton, MouseEventButtons, PointerCoords, UiEvent,
+    BlitzPointerEvent, BlitzPointerId, BlitzWheelDelta, BlitzWheelEvent, MouseEventButton,
+    MouseEventButtons, PointerCoords, UiEvent,
 };
 use dioxus_html::Modifiers;
 
+use crate::keyboard::DioxusFocusedDocument;
+
 #[derive(Resource, Default)]
 pub struct MouseState {
     pub x: f32,
@@ -29,13 +36,60 @@ pub struct UiPickState {
 /// Holds the per-frame picking result for world-space dioxus UI quads.
 #[derive(Resource, Default)]
 pub struct WorldSpacePickingState {
+    /// Quad under the mouse.
     pub pick: Option<UiPickState>,
+    /// Quads under each touch, by touch id.
+    pub touch_picks: HashMap<u64, UiPickState>,
 }
 
 /// Communicates which input space handled cursor events this frame.
 #[derive(Resource, Default)]
 pub struct MouseMessageRouting {
     pub window_space_handled: bool,
+    /// Scroll wheel messages this frame were sent to a document.
+    pub wheel_handled: bool,
+}
+
+/// Run condition for systems that read [`MouseWheel`], like camera zoom, so they skip
+/// scrolls a document already handled. Scrolls are routed in `PreUpdate`, so this is
+/// up to date for systems in `Update`.
+pub fn no_dioxus_wheel_scroll(routing: Res<MouseMessageRouting>) -> bool {
+    !routing.wheel_handled
+}
+
+/// The first world-space UI quad in `hits`, with the hit in the quad's pixel coordinates.
+fn pick_world_quad(
+    hits: &PointerHits,
+    world_quads: &Query<(&DioxusUiQuad, &GlobalTransform), Without<DioxusWindowUiQuad>>,
+) -> Option<UiPickState> {
+    for (entity, hit_data) in &hits.picks {
+        if let Ok((quad, transform)) = world_quads.get(*entity) {
+            let Some(world_pos) = hit_data.position else {
+                continue;
+            };
+            let Some(half) = quad.local_half_extents else {
+                continue;
+            };
+            let Some(wh) = quad.computed_wh else {
+                continue;
+            };
+
+            let local_pos = transform.affine().inverse().transform_point3(world_pos);
+
+            let u = (local_pos.x + half.x) / (2.0 * half.x);
+            let v = (local_pos.y + half.y) / (2.0 * half.y);
+
+            let pixel_x = u * wh.x;
+            let pixel_y = (1.0 - v) * wh.y;
+
+            return Some(UiPickState {
+                hit_entity: *entity,
+                local_cords: Vec2::new(pixel_x, pixel_y),
+                world_cords: world_pos,
+            });
+        }
+    }
+    None
 }
 
 pub(crate) fn update_world_space_picking(
@@ -46,33 +100,15 @@ pub(crate) fn update_world_space_picking(
     *picking_state = WorldSpacePickingState::default();
 
     for hits in pointer_hits.read() {
-        for (entity, hit_data) in &hits.picks {
-            if let Ok((quad, transform)) = world_quads.get(*entity) {
-                let Some(world_pos) = hit_data.position else {
-                    continue;
-                };
-                let Some(half) = quad.local_half_extents else {
-                    continue;
-                };
-                let Some(wh) = quad.computed_wh else {
-                    continue;
-                };
-
-                let local_pos = transform.affine().inverse().transform_point3(world_pos);
-
-                let u = (local_pos.x + half.x) / (2.0 * half.x);
-                let v = (local_pos.y + half.y) / (2.0 * half.y);
-
-                let pixel_x = u * wh.x;
-                let pixel_y = (1.0 - v) * wh.y;
-
-                picking_state.pick = Some(UiPickState {
-                    hit_entity: *entity,
-                    local_cords: Vec2::new(pixel_x, pixel_y),
-                    world_cords: hit_data.position.unwrap(),
-                });
-                break;
+        let Some(pick) = pick_world_quad(hits, &world_quads) else {
+            continue;
+        };
+        match hits.pointer {
+            PointerId::Mouse => picking_state.pick = Some(pick),
+            PointerId::Touch(id) => {
+                picking_state.touch_picks.insert(id, pick);
             }
+            PointerId::Custom(_) => {}
         }
     }
 }
@@ -121,7 +157,7 @@ pub(crate) fn window_space_mouse_messages(
             details: Default::default(),
         };
 
-        if let Some(worker) = registry.workers.get(&window_ui) {
+        if let Some(worker) = registry.worker_for(window_ui) {
             let _ = worker
                 .input_tx
                 .try_send((window_ui, UiEvent::PointerMove(pointer_event.clone())));
@@ -131,7 +167,7 @@ pub(crate) fn window_space_mouse_messages(
 
 /// Sends cursor-move events to the world-space picked entity.
 pub(crate) fn world_space_mouse_messages(
-    mut registry: NonSendMut<VdomThreadRegistry>,
+    registry: NonSendMut<VdomThreadRegistry>,
     mut cursor_moved: MessageReader<CursorMoved>,
     mut mouse_state: ResMut<MouseState>,
     routing: Res<MouseMessageRouting>,
@@ -150,7 +186,7 @@ pub(crate) fn world_space_mouse_messages(
         mouse_state.y = cursor_event.position.y;
 
         if let Some(pick) = &picking_state.pick {
-            if let Some(worker) = registry.workers.get_mut(&pick.hit_entity) {
+            if let Some(worker) = registry.worker_for(pick.hit_entity) {
                 let local_event = BlitzPointerEvent {
                     id: BlitzPointerId::Mouse,
                     is_primary: true,
@@ -177,11 +213,12 @@ pub(crate) fn world_space_mouse_messages(
 
 /// Sends button press and release events, routing based on the active pick space.
 pub(crate) fn blitz_mouse_button_handling(
-    mut registry: NonSendMut<VdomThreadRegistry>,
+    registry: NonSendMut<VdomThreadRegistry>,
     mouse_button_input_events: ResMut<Messages<MouseButtonInput>>,
     mut mouse_state: ResMut<MouseState>,
     pick_state: Res<DioxusUiPickState>,
     picking_state: Res<WorldSpacePickingState>,
+    mut focused: ResMut<DioxusFocusedDocument>,
     window_ui: Query<Entity, With<DioxusWindowUiQuad>>,
 ) {
     if mouse_button_input_events.is_empty() {
@@ -205,6 +242,18 @@ pub(crate) fn blitz_mouse_button_handling(
         match event.state {
             ButtonState::Pressed => {
                 mouse_state.buttons |= buttons_blitz;
+                // Pressing on a document focuses it, pressing on the game blurs it
+                let pressed = match pick_state.active {
+                    DioxusUiPickFilter::WINDOW_SPACE => window_ui.single().ok(),
+                    DioxusUiPickFilter::WORLD_SPACE => {
+                        picking_state.pick.as_ref().map(|pick| pick.hit_entity)
+                    }
+                    _ => None,
+                };
+                focused.set_if_neq(DioxusFocusedDocument {
+                    entity: pressed,
+                    ..*focused
+                });
             }
             ButtonState::Released => {
                 mouse_state.buttons &= !buttons_blitz;
@@ -234,14 +283,14 @@ pub(crate) fn blitz_mouse_button_handling(
                     ButtonState::Released => UiEvent::PointerUp(pointer_event),
                 };
                 if let Ok(window_ui) = window_ui.single() {
-                    if let Some(worker) = registry.workers.get(&window_ui) {
+                    if let Some(worker) = registry.worker_for(window_ui) {
                         let _ = worker.input_tx.try_send((window_ui, ui_event));
                     }
                 }
             }
             DioxusUiPickFilter::WORLD_SPACE => {
                 if let Some(pick) = &picking_state.pick {
-                    if let Some(worker) = registry.workers.get_mut(&pick.hit_entity) {
+                    if let Some(worker) = registry.worker_for(pick.hit_entity) {
                         let pointer_event = BlitzPointerEvent {
                             id: BlitzPointerId::Mouse,
                             is_primary: true,
@@ -270,3 +319,64 @@ pub(crate) fn blitz_mouse_button_handling(
         }
     }
 }
+
+/// Sends scroll wheel events to the ui under the cursor, routing based on the active pick space.
+///
+/// Scrolls sent to a ui are recorded in [`MouseMessageRouting::wheel_handled`], see
+/// [`no_dioxus_wheel_scroll`].
+pub(crate) fn blitz_mouse_wheel_handling(
+    registry: NonSendMut<VdomThreadRegistry>,
+    mut mouse_wheel_events: MessageReader<MouseWheel>,
+    mut routing: ResMut<MouseMessageRouting>,
+    mouse_state: Res<MouseState>,
+    pick_state: Res<DioxusUiPickState>,
+    picking_state: Res<WorldSpacePickingState>,
+    window_ui: Query<Entity, With<DioxusWindowUiQuad>>,
+) {
+    routing.wheel_handled = false;
+    if mouse_wheel_events.is_empty() {
+        return;
+    }
+
+    let target = match pick_state.active {
+        DioxusUiPickFilter::WINDOW_SPACE => window_ui
+            .single()
+            .ok()
+            .map(|window_ui| (window_ui, Vec2::new(mouse_state.x, mouse_state.y))),
+        DioxusUiPickFilter::WORLD_SPACE => picking_state
+            .pick
+            .as_ref()
+            .map(|pick| (pick.hit_entity, pick.local_cords)),
+        _ => None,
+    };
+    let Some(((target, coords), worker)) =
+        target.and_then(|target| Some((target, registry.worker_for(target.0)?)))
+    else {
+        mouse_wheel_events.clear();
+        return;
+    };
+
+    routing.wheel_handled = true;
+    for event in mouse_wheel_events.read() {
+        let delta = match event.unit {
+            MouseScrollUnit::Line => BlitzWheelDelta::Lines(event.x as f64, event.y as f64),
+            MouseScrollUnit::Pixel => BlitzWheelDelta::Pixels(event.x as f64, event.y as f64),
+        };
+        let wheel_event = BlitzWheelEvent {
+            delta,
+            coords: PointerCoords {
+                page_x: coords.x,
+                page_y: coords.y,
+                screen_x: coords.x,
+                screen_y: coords.y,
+                client_x: coords.x,
+                client_y: coords.y,
+            },
+            buttons: mouse_state.buttons,
+            mods: mouse_state.mods,
+        };
+        let _ = worker
+            .input_tx
+            .try_send((target, UiEvent::Wheel(wheel_event)));
+    }
+}
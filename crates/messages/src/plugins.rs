@@ -5,14 +5,19 @@ use bevy_ecs::schedule::IntoScheduleConfigs;
 use bevy_input::InputSystems;
 use bevy_picking::PickingSystems;
 
+use crate::gamepad::DioxusGamepadNavigation;
+use crate::ime::{DioxusImeState, handle_ime_messages, sync_ime_window};
+use crate::keyboard::{DioxusFocusedDocument, sync_focused_document};
 use crate::mouse::MouseMessageRouting;
 use crate::mouse::MouseState;
 use crate::mouse::WorldSpacePickingState;
+use crate::touch::TouchState;
 
 use super::{
-    keyboard::handle_keyboard_messages, mouse::blitz_mouse_button_handling,
+    gamepad::gamepad_focus_navigation, keyboard::handle_keyboard_messages,
+    mouse::blitz_mouse_button_handling, mouse::blitz_mouse_wheel_handling,
     mouse::update_world_space_picking, mouse::window_space_mouse_messages,
-    mouse::world_space_mouse_messages,
+    mouse::world_space_mouse_messages, touch::blitz_touch_handling,
 };
 
 pub struct DioxusEventSyncPlugin;
@@ -23,6 +28,10 @@ impl Plugin for DioxusEventSyncPlugin {
             .init_resource::<DioxusUiPickState>()
             .init_resource::<MouseState>()
             .init_resource::<MouseMessageRouting>()
+            .init_resource::<TouchState>()
+            .init_resource::<DioxusGamepadNavigation>()
+            .init_resource::<DioxusFocusedDocument>()
+            .init_resource::<DioxusImeState>()
             .add_systems(
                 PreUpdate,
                 (
@@ -30,7 +39,13 @@ impl Plugin for DioxusEventSyncPlugin {
                     window_space_mouse_messages.after(InputSystems),
                     world_space_mouse_messages.after(window_space_mouse_messages),
                     blitz_mouse_button_handling.after(world_space_mouse_messages),
+                    blitz_mouse_wheel_handling.after(blitz_mouse_button_handling),
+                    blitz_touch_handling.after(update_world_space_picking),
+                    sync_focused_document,
+                    handle_ime_messages.after(InputSystems),
                     handle_keyboard_messages.after(InputSystems),
+                    gamepad_focus_navigation.after(InputSystems),
+                    sync_ime_window,
                 )
                     .chain(),
             );
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use bevy_dioxus_render::worker::VdomThreadRegistry;
+use bevy_dioxus_render::{
+    DioxusUiHitTest, DioxusUiPickFilter, DioxusUiPickState, DioxusWindowUiQuad,
+};
+use bevy_ecs::prelude::*;
+use bevy_input::touch::{ForceTouch, TouchInput, TouchPhase};
+use bevy_math::prelude::*;
+use blitz_traits::events::{
+    BlitzPointerEvent, BlitzPointerId, MouseEventButton, MouseEventButtons, PointerCoords,
+    PointerDetails, UiEvent,
+};
+
+use crate::keyboard::DioxusFocusedDocument;
+use crate::mouse::{MouseState, WorldSpacePickingState};
+
+/// A touch pressed on a document, which gets every event for the touch until it lifts.
+pub struct ActiveTouch {
+    pub entity: Entity,
+    pub world_space: bool,
+    pub is_primary: bool,
+}
+
+/// Touches currently pressed on documents, by touch id.
+#[derive(Resource, Default)]
+pub struct TouchState {
+    pub touches: HashMap<u64, ActiveTouch>,
+    /// Document the last touch started on, until the document reports its hit-test, which
+    /// decides whether the touch focuses it.
+    focus_pending: Option<Entity>,
+}
+
+fn touch_pointer_event(
+    id: u64,
+    is_primary: bool,
+    position: Vec2,
+    pressed: bool,
+    force: Option<ForceTouch>,
+    mouse_state: &MouseState,
+) -> BlitzPointerEvent {
+    let pressure = match force {
+        Some(ForceTouch::Normalized(force)) => force,
+        Some(ForceTouch::Calibrated {
+            force,
+            max_possible_force,
+            ..
+        }) => force / max_possible_force,
+        None if pressed => 0.5,
+        None => 0.0,
+    };
+    BlitzPointerEvent {
+        id: BlitzPointerId::Finger(id),
+        is_primary,
+        coords: PointerCoords {
+            page_x: position.x,
+            page_y: position.y,
+            screen_x: position.x,
+            screen_y: position.y,
+            client_x: position.x,
+            client_y: position.y,
+        },
+        button: MouseEventButton::Main,
+        buttons: if pressed {
+            MouseEventButtons::Primary
+        } else {
+            MouseEventButtons::None
+        },
+        mods: mouse_state.mods,
+        details: PointerDetails {
+            pressure,
+            ..Default::default()
+        },
+    }
+}
+
+/// Sends touches to the document they started on, as finger pointer events.
+///
+/// A touch goes to the world-space quad under it, unless the window overlay caught the
+/// last pointer, and to the window ui otherwise. Blitz has no `pointercancel`, so a touch
+/// that is canceled, or slides off its world-space quad, is released outside the document,
+/// which clears `:active` without clicking.
+///
+/// Like a mouse press, a touch focuses its document if an element there catches it, and
+/// blurs the focused document otherwise. Touches have no hover to hit-test ahead of time,
+/// so this waits for the document to report its hit-test of the touch, rather than reading
+/// [`DioxusUiPickState`], which the touch may leave unchanged.
+pub(crate) fn blitz_touch_handling(
+    registry: NonSendMut<VdomThreadRegistry>,
+    mut touch_events: MessageReader<TouchInput>,
+    mut hit_tests: MessageReader<DioxusUiHitTest>,
+    mut touch_state: ResMut<TouchState>,
+    mouse_state: Res<MouseState>,
+    pick_state: Res<DioxusUiPickState>,
+    picking_state: Res<WorldSpacePickingState>,
+    mut focused: ResMut<DioxusFocusedDocument>,
+    window_ui: Query<Entity, With<DioxusWindowUiQuad>>,
+) {
+    for hit in hit_tests.read() {
+        if touch_state.focus_pending == Some(hit.entity) {
+            touch_state.focus_pending = None;
+            focused.set_if_neq(DioxusFocusedDocument {
+                entity: hit.caught.then_some(hit.entity),
+                ..*focused
+            });
+        }
+    }
+
+    for event in touch_events.read() {
+        if event.phase == TouchPhase::Started {
+            let world_pick = picking_state
+                .touch_picks
+                .get(&event.id)
+                .filter(|_| pick_state.active != DioxusUiPickFilter::WINDOW_SPACE);
+            let (entity, world_space) = match world_pick {
+                Some(pick) => (pick.hit_entity, true),
+                None => match window_ui.single() {
+                    Ok(window_ui) => (window_ui, false),
+                    Err(_) => continue,
+                },
+            };
+            touch_state.focus_pending = Some(entity);
+            let is_primary = !touch_state.touches.values().any(|touch| touch.is_primary);
+            touch_state.touches.insert(
+                event.id,
+                ActiveTouch {
+                    entity,
+                    world_space,
+                    is_primary,
+                },
+            );
+        }
+
+        let Some(touch) = touch_state.touches.get(&event.id) else {
+            continue;
+        };
+        let Some(worker) = registry.worker_for(touch.entity) else {
+            // Nothing will hit-test the touch
+            if touch_state.focus_pending == Some(touch.entity) {
+                touch_state.focus_pending = None;
+            }
+            touch_state.touches.remove(&event.id);
+            continue;
+        };
+        let position = if touch.world_space {
+            picking_state
+                .touch_picks
+                .get(&event.id)
+                .filter(|pick| pick.hit_entity == touch.entity)
+                .map(|pick| pick.local_cords)
+        } else {
+            Some(event.position)
+        };
+
+        let pointer_event = |position, pressed| {
+            touch_pointer_event(
+                event.id,
+                touch.is_primary,
+                position,
+                pressed,
+                event.force,
+                &mouse_state,
+            )
+        };
+        let ui_events = match (event.phase, position) {
+            (TouchPhase::Started, Some(position)) => vec![
+                UiEvent::PointerMove(pointer_event(position, false)),
+                UiEvent::PointerDown(pointer_event(position, true)),
+            ],
+            (TouchPhase::Moved, Some(position)) => {
+                vec![UiEvent::PointerMove(pointer_event(position, true))]
+            }
+            (TouchPhase::Ended, Some(position)) => {
+                vec![UiEvent::PointerUp(pointer_event(position, false))]
+            }
+            // Canceled, or off the quad the touch started on
+            _ => vec![UiEvent::PointerUp(pointer_event(
+                Vec2::new(-1.0, -1.0),
+                false,
+            ))],
+        };
+        let released = matches!(ui_events.last(), Some(UiEvent::PointerUp(_)));
+
+        for ui_event in ui_events {
+            let _ = worker.input_tx.try_send((touch.entity, ui_event));
+        }
+        if released {
+            touch_state.touches.remove(&event.id);
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! Fetched resources cached by url and shared by every document.
+//!
+//! Requests for a url that is already loading wait on that load instead of starting
+//! another. `bevy://` urls skip the cache, since the asset server already shares them.
+//! Fragments don't change what a url loads, so urls are cached without them.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bevy_ecs::prelude::*;
+use blitz_traits::net::Url;
+use bytes::Bytes;
+
+use crate::net_provider::FetchResponder;
+
+/// Starts loading a url, completing the responder it is given.
+pub(crate) type Load = Arc<dyn Fn(FetchResponder) + Send + Sync>;
+
+enum CacheEntry {
+    Loading {
+        waiters: Vec<FetchResponder>,
+        /// Invalidated while loading, so the result isn't kept.
+        stale: bool,
+        /// Restarts the load if it is given up while a request still waits on it.
+        load: Load,
+    },
+    Loaded {
+        bytes: Bytes,
+        last_used: u64,
+    },
+}
+
+struct FetchCacheState {
+    entries: HashMap<String, CacheEntry>,
+    max_bytes: usize,
+    max_entry_bytes: usize,
+    total_bytes: usize,
+    clock: u64,
+}
+
+/// The url without its fragment.
+fn cache_key(url: &str) -> &str {
+    url.split_once('#').map_or(url, |(key, _)| key)
+}
+
+impl FetchCacheState {
+    fn remove_loaded(&mut self, url: &str) {
+        if let Some(CacheEntry::Loaded { bytes, .. }) = self.entries.get(url) {
+            self.total_bytes -= bytes.len();
+            self.entries.remove(url);
+        }
+    }
+
+    /// Drop the least recently used resources until the cache fits in `max_bytes`.
+    fn evict(&mut self) {
+        while self.total_bytes > self.max_bytes {
+            let oldest = self
+                .entries
+                .iter()
+                .filter_map(|(url, entry)| match entry {
+                    CacheEntry::Loaded { last_used, .. } => Some((*last_used, url)),
+                    CacheEntry::Loading { .. } => None,
+                })
+                .min()
+                .map(|(_, url)| url.clone());
+            let Some(oldest) = oldest else {
+                return;
+            };
+            self.remove_loaded(&oldest);
+        }
+    }
+}
+
+/// Resources fetched by documents, by url. Clones share the same cache.
+#[derive(Resource, Clone)]
+pub struct DioxusFetchCache(Arc<Mutex<FetchCacheState>>);
+
+impl Default for DioxusFetchCache {
+    fn default() -> Self {
+        Self::new(64 * 1024 * 1024, 8 * 1024 * 1024)
+    }
+}
+
+impl DioxusFetchCache {
+    /// Cache up to `max_bytes` of resources. Resources larger than `max_entry_bytes` are
+    /// shared with requests made while they load, but not kept.
+    pub fn new(max_bytes: usize, max_entry_bytes: usize) -> Self {
+        Self(Arc::new(Mutex::new(FetchCacheState {
+            entries: HashMap::new(),
+            max_bytes,
+            max_entry_bytes,
+            total_bytes: 0,
+            clock: 0,
+        })))
+    }
+
+    /// Forget the cached resource at `url`, so the next request loads it again.
+    pub fn invalidate(&self, url: &str) {
+        let url = cache_key(url);
+        let mut state = self.0.lock().unwrap();
+        match state.entries.get_mut(url) {
+            Some(CacheEntry::Loading { stale, .. }) => *stale = true,
+            Some(CacheEntry::Loaded { .. }) => state.remove_loaded(url),
+            None => {}
+        }
+    }
+
+    /// Forget every cached resource whose url path ends with `path`, e.g. an asset path
+    /// reported by the dioxus devserver.
+    pub fn invalidate_path(&self, path: &str) {
+        let urls: Vec<String> = self
+            .0
+            .lock()
+            .unwrap()
+            .entries
+            .keys()
+            .filter(|url| {
+                Url::parse(url)
+                    .map(|url| url.path().ends_with(path))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+        for url in urls {
+            self.invalidate(&url);
+        }
+    }
+
+    pub fn clear(&self) {
+        let urls: Vec<String> = self.0.lock().unwrap().entries.keys().cloned().collect();
+        for url in urls {
+            self.invalidate(&url);
+        }
+    }
+
+    /// Serve `responder` from the cache, or queue it behind a load already running.
+    /// Otherwise starts a load with `load`, which completes every request for the url.
+    pub(crate) fn fetch(&self, responder: FetchResponder, load: Load) {
+        let key = cache_key(responder.url()).to_string();
+        let mut state = self.0.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        match state.entries.get_mut(&key) {
+            Some(CacheEntry::Loaded { bytes, last_used }) => {
+                *last_used = clock;
+                let bytes = bytes.clone();
+                drop(state);
+                responder.respond(bytes);
+            }
+            Some(CacheEntry::Loading { waiters, .. }) => waiters.push(responder),
+            None => {
+                state.entries.insert(
+                    key.clone(),
+                    CacheEntry::Loading {
+                        waiters: vec![responder],
+                        stale: false,
+                        load: load.clone(),
+                    },
+                );
+                drop(state);
+                load(FetchResponder::cached(key, self.clone()));
+            }
+        }
+    }
+
+    /// Whether any request waiting on the load of `url` still wants it.
+    pub(crate) fn is_wanted(&self, url: &str) -> bool {
+        match self.0.lock().unwrap().entries.get(url) {
+            Some(CacheEntry::Loading { waiters, .. }) => {
+                waiters.iter().any(FetchResponder::is_wanted)
+            }
+            _ => false,
+        }
+    }
+
+    /// Finish the load of `url`, completing every request waiting on it.
+    pub(crate) fn complete(&self, url: &str, result: Result<Bytes, String>) {
+        let mut state = self.0.lock().unwrap();
+        let Some(CacheEntry::Loading { waiters, stale, .. }) = state.entries.remove(url) else {
+            return;
+        };
+        if let Ok(bytes) = &result
+            && !stale
+            && bytes.len() <= state.max_entry_bytes
+        {
+            state.clock += 1;
+            state.total_bytes += bytes.len();
+            let last_used = state.clock;
+            state.entries.insert(
+                url.to_string(),
+                CacheEntry::Loaded {
+                    bytes: bytes.clone(),
+                    last_used,
+                },
+            );
+            state.evict();
+        }
+        drop(state);
+
+        for waiter in waiters {
+            match &result {
+                Ok(bytes) => waiter.respond(bytes.clone()),
+                Err(reason) => waiter.fail(reason),
+            }
+        }
+    }
+
+    /// The load of `url` was dropped without finishing, e.g. because no document
+    /// wanted it anymore. A request may have joined since that was checked, so the load
+    /// restarts if any waiting request is still wanted, and the rest fail. The next
+    /// request loads it again otherwise.
+    pub(crate) fn abandon(&self, url: &str) {
+        let mut state = self.0.lock().unwrap();
+        if !matches!(state.entries.get(url), Some(CacheEntry::Loading { .. })) {
+            return;
+        }
+        let Some(CacheEntry::Loading { waiters, load, .. }) = state.entries.remove(url) else {
+            return;
+        };
+        if waiters.iter().any(FetchResponder::is_wanted) {
+            state.entries.insert(
+                url.to_string(),
+                CacheEntry::Loading {
+                    waiters,
+                    stale: false,
+                    load: load.clone(),
+                },
+            );
+            drop(state);
+            load(FetchResponder::cached(url.to_string(), self.clone()));
+            return;
+        }
+        drop(state);
+        for waiter in waiters {
+            waiter.fail("the load was cancelled");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Weak;
+
+    use blitz_traits::net::NetHandler;
+
+    use super::*;
+    use crate::net_provider::LoadFailures;
+
+    /// Bodies delivered to one request.
+    #[derive(Clone, Default)]
+    struct Received(Arc<Mutex<Vec<Bytes>>>);
+
+    impl Received {
+        fn bodies(&self) -> Vec<Bytes> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl NetHandler for Received {
+        fn bytes(self: Box<Self>, _resolved_url: String, bytes: Bytes) {
+            self.0.lock().unwrap().push(bytes);
+        }
+    }
+
+    fn responder(url: &str, received: &Received, document: Weak<()>) -> FetchResponder {
+        let (sender, _) = crossbeam_channel::unbounded();
+        FetchResponder::new(
+            url.to_string(),
+            Box::new(received.clone()),
+            LoadFailures::new(Entity::PLACEHOLDER, sender),
+            document,
+        )
+    }
+
+    /// Loads the cache started, waiting to be completed.
+    #[derive(Clone, Default)]
+    struct Loads(Arc<Mutex<Vec<FetchResponder>>>);
+
+    impl Loads {
+        fn load(&self) -> Load {
+            let loads = self.clone();
+            Arc::new(move |responder| loads.0.lock().unwrap().push(responder))
+        }
+
+        fn take(&self) -> Option<FetchResponder> {
+            self.0.lock().unwrap().pop()
+        }
+    }
+
+    /// Request `url`, returning whether it had to be loaded.
+    fn needs_load(cache: &DioxusFetchCache, url: &str) -> bool {
+        let loads = Loads::default();
+        cache.fetch(
+            responder(url, &Received::default(), Weak::new()),
+            loads.load(),
+        );
+        loads.take().is_some()
+    }
+
+    fn load(cache: &DioxusFetchCache, url: &str, body: &'static [u8]) {
+        let loads = Loads::default();
+        cache.fetch(
+            responder(url, &Received::default(), Weak::new()),
+            loads.load(),
+        );
+        loads.take().unwrap().respond(Bytes::from_static(body));
+    }
+
+    #[test]
+    fn concurrent_requests_share_one_load() {
+        let cache = DioxusFetchCache::default();
+        let url = "https://example.com/style.css";
+        let loads = Loads::default();
+        let (first, second) = (Received::default(), Received::default());
+
+        cache.fetch(responder(url, &first, Weak::new()), loads.load());
+        cache.fetch(responder(url, &second, Weak::new()), loads.load());
+        let load = loads.take().unwrap();
+        assert!(loads.take().is_none());
+        load.respond(Bytes::from_static(b"body"));
+        assert_eq!(first.bodies(), vec![Bytes::from_static(b"body")]);
+        assert_eq!(second.bodies(), vec![Bytes::from_static(b"body")]);
+
+        let third = Received::default();
+        cache.fetch(responder(url, &third, Weak::new()), loads.load());
+        assert!(loads.take().is_none());
+        assert_eq!(third.bodies(), vec![Bytes::from_static(b"body")]);
+    }
+
+    #[test]
+    fn fragments_share_an_entry() {
+        let cache = DioxusFetchCache::default();
+        load(&cache, "https://example.com/icon.png", b"icon");
+        assert!(!needs_load(
+            &cache,
+            "https://example.com/icon.png#bevy-reload-1"
+        ));
+
+        cache.invalidate("https://example.com/icon.png#bevy-reload-1");
+        assert!(needs_load(&cache, "https://example.com/icon.png"));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let cache = DioxusFetchCache::new(10, 10);
+        load(&cache, "https://example.com/a", b"aaaa");
+        load(&cache, "https://example.com/b", b"bbbb");
+        assert!(!needs_load(&cache, "https://example.com/a"));
+
+        load(&cache, "https://example.com/c", b"cccc");
+        assert!(needs_load(&cache, "https://example.com/b"));
+        assert!(!needs_load(&cache, "https://example.com/a"));
+        assert!(!needs_load(&cache, "https://example.com/c"));
+    }
+
+    #[test]
+    fn does_not_keep_large_resources() {
+        let cache = DioxusFetchCache::new(100, 2);
+        load(&cache, "https://example.com/large", b"large");
+        assert!(needs_load(&cache, "https://example.com/large"));
+    }
+
+    #[test]
+    fn abandoned_loads_fail_requests_no_document_wants() {
+        let cache = DioxusFetchCache::default();
+        let url = "https://example.com/a";
+        let loads = Loads::default();
+        let received = Received::default();
+        cache.fetch(responder(url, &received, Weak::new()), loads.load());
+        drop(loads.take());
+
+        // Failed loads complete empty
+        assert_eq!(received.bodies(), vec![Bytes::new()]);
+        assert!(loads.take().is_none());
+        assert!(needs_load(&cache, url));
+    }
+
+    #[test]
+    fn abandoned_loads_restart_for_requests_that_joined() {
+        let cache = DioxusFetchCache::default();
+        let url = "https://example.com/a";
+        let loads = Loads::default();
+        let (first, second) = (Received::default(), Received::default());
+        let document = Arc::new(());
+
+        cache.fetch(responder(url, &first, Weak::new()), loads.load());
+        let load = loads.take().unwrap();
+        assert!(!load.is_wanted());
+        // Joins after the load was found unwanted, before it is dropped
+        cache.fetch(
+            responder(url, &second, Arc::downgrade(&document)),
+            loads.load(),
+        );
+        drop(load);
+
+        let restarted = loads.take().unwrap();
+        restarted.respond(Bytes::from_static(b"body"));
+        assert_eq!(first.bodies(), vec![Bytes::from_static(b"body")]);
+        assert_eq!(second.bodies(), vec![Bytes::from_static(b"body")]);
+    }
+
+    #[test]
+    fn invalidated_while_loading_is_not_kept() {
+        let cache = DioxusFetchCache::default();
+        let url = "https://example.com/a";
+        let loads = Loads::default();
+        let received = Received::default();
+        cache.fetch(responder(url, &received, Weak::new()), loads.load());
+        cache.invalidate(url);
+        loads.take().unwrap().respond(Bytes::from_static(b"old"));
+
+        assert_eq!(received.bodies(), vec![Bytes::from_static(b"old")]);
+        assert!(needs_load(&cache, url));
+    }
+
+    #[test]
+    fn invalidate_path_forgets_matching_urls() {
+        let cache = DioxusFetchCache::default();
+        load(&cache, "http://localhost/assets/main.css", b"main");
+        load(&cache, "http://localhost/assets/other.css", b"other");
+
+        cache.invalidate_path("/assets/main.css");
+        assert!(needs_load(&cache, "http://localhost/assets/main.css"));
+        assert!(!needs_load(&cache, "http://localhost/assets/other.css"));
+    }
+}
@@ -0,0 +1,51 @@
+//! The single devserver connection shared by every document.
+
+use bevy_dioxus_interop::DioxusMessage;
+use bevy_dioxus_tracing::debug;
+use bevy_ecs::prelude::*;
+use crossbeam_channel::Receiver;
+use dioxus_devtools::DevserverMsg;
+
+use crate::cache::DioxusFetchCache;
+use crate::worker::{VdomCommand, VdomThreadRegistry};
+
+/// Messages from the devserver connection opened by the plugin.
+#[derive(Resource)]
+pub(crate) struct DevserverMessages(Receiver<DevserverMsg>);
+
+impl DevserverMessages {
+    /// Connect to the devserver. Does nothing when the app wasn't launched by `dx`.
+    pub(crate) fn connect() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        dioxus_devtools::connect(move |msg| {
+            let _ = sender.send(msg);
+        });
+        Self(receiver)
+    }
+}
+
+/// Sends devserver messages to every worker.
+pub(crate) fn forward_devserver_messages(
+    messages: Res<DevserverMessages>,
+    fetch_cache: Res<DioxusFetchCache>,
+    registry: NonSend<VdomThreadRegistry>,
+) {
+    for msg in messages.0.try_iter() {
+        if let DevserverMsg::HotReload(hotreload) = &msg {
+            for asset_path in &hotreload.assets {
+                if let Some(path) = asset_path.to_str() {
+                    fetch_cache.invalidate_path(path);
+                }
+            }
+        }
+        debug!(
+            "forwarding devserver message to {} documents",
+            registry.workers.len()
+        );
+        for worker in registry.workers.values() {
+            let _ = worker
+                .cmd_tx
+                .try_send(VdomCommand::Message(DioxusMessage::Devserver(msg.clone())));
+        }
+    }
+}
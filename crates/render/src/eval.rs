@@ -0,0 +1,167 @@
+//! `document::eval` for documents rendered in bevy.
+//!
+//! There is no javascript engine, so the script string is handed to bevy instead. A handler
+//! registered for the exact script with [`DioxusEvalAppExt::register_dioxus_eval`] receives it
+//! first, otherwise a [`DioxusEvalRequested`] event is triggered on the ui quad for observers.
+//! Both exchange messages with the component through [`DioxusEval`], mirroring the `Eval`
+//! send and recv API.
+//!
+//! ```ignore
+//! app.register_dioxus_eval("inventory", |eval: DioxusEval| {
+//!     eval.send(serde_json::json!({ "slots": 12 }));
+//!     eval.finish(serde_json::Value::Null);
+//! });
+//!
+//! // in a component
+//! let mut eval = document::eval("inventory");
+//! let slots = eval.recv::<serde_json::Value>().await;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bevy_app::App;
+use bevy_ecs::prelude::*;
+use crossbeam_channel::{Receiver, Sender};
+use dioxus_document::{Eval, EvalError, Evaluator};
+use futures_channel::mpsc::{UnboundedReceiver, UnboundedSender};
+use futures_util::StreamExt;
+use generational_box::UnsyncStorage;
+use serde_json::Value;
+
+use crate::Channel;
+
+/// Bevy's end of one `document::eval` call. Clones talk to the same call, which finishes
+/// once [`DioxusEval::finish`] is called or every clone is dropped.
+#[derive(Clone, Debug)]
+pub struct DioxusEval {
+    /// Ui quad of the document that called eval.
+    pub entity: Entity,
+    pub script: String,
+    /// Messages the component sent with `Eval::send`.
+    pub from_document: Receiver<Value>,
+    to_document: UnboundedSender<Value>,
+    result: UnboundedSender<Value>,
+}
+
+impl DioxusEval {
+    /// Send a message the component receives with `Eval::recv`. Returns false if the
+    /// component dropped its `Eval`.
+    pub fn send(&self, value: Value) -> bool {
+        self.to_document.unbounded_send(value).is_ok()
+    }
+
+    pub fn try_recv(&self) -> Option<Value> {
+        self.from_document.try_recv().ok()
+    }
+
+    /// Resolve the component's `Eval` with `value`.
+    pub fn finish(&self, value: Value) {
+        let _ = self.result.unbounded_send(value);
+        self.result.close_channel();
+    }
+}
+
+/// Triggered on a ui quad when its document calls `document::eval` with a script that has
+/// no registered handler.
+#[derive(EntityEvent, Clone, Debug)]
+pub struct DioxusEvalRequested {
+    pub entity: Entity,
+    pub eval: DioxusEval,
+}
+
+pub type DioxusEvalHandler = Arc<dyn Fn(DioxusEval) + Send + Sync>;
+
+/// Handlers for `document::eval` calls, by script.
+#[derive(Resource, Clone, Default)]
+pub struct DioxusEvalHandlers(pub HashMap<String, DioxusEvalHandler>);
+
+pub trait DioxusEvalAppExt {
+    /// Handle `document::eval(script)` calls from every document with `handler`.
+    fn register_dioxus_eval(
+        &mut self,
+        script: impl Into<String>,
+        handler: impl Fn(DioxusEval) + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl DioxusEvalAppExt for App {
+    fn register_dioxus_eval(
+        &mut self,
+        script: impl Into<String>,
+        handler: impl Fn(DioxusEval) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.init_resource::<DioxusEvalHandlers>();
+        self.world_mut()
+            .resource_mut::<DioxusEvalHandlers>()
+            .0
+            .insert(script.into(), Arc::new(handler));
+        self
+    }
+}
+
+pub(crate) type EvalChannel = Channel<DioxusEval>;
+
+/// Hands eval calls from every document to their handler, or to observers.
+pub(crate) fn dispatch_evals(
+    channel: Res<EvalChannel>,
+    handlers: Res<DioxusEvalHandlers>,
+    mut commands: Commands,
+) {
+    for eval in channel.receiver.try_iter() {
+        match handlers.0.get(&eval.script) {
+            Some(handler) => handler(eval),
+            None => commands.trigger(DioxusEvalRequested {
+                entity: eval.entity,
+                eval,
+            }),
+        }
+    }
+}
+
+/// The component's end of an eval call.
+struct BevyEvaluator {
+    to_bevy: Sender<Value>,
+    from_bevy: UnboundedReceiver<Value>,
+    result: UnboundedReceiver<Value>,
+}
+
+impl Evaluator for BevyEvaluator {
+    fn send(&self, data: Value) -> Result<(), EvalError> {
+        self.to_bevy.send(data).map_err(|_| EvalError::Finished)
+    }
+
+    fn poll_recv(&mut self, context: &mut Context<'_>) -> Poll<Result<Value, EvalError>> {
+        self.from_bevy
+            .poll_next_unpin(context)
+            .map(|value| value.ok_or(EvalError::Finished))
+    }
+
+    fn poll_join(&mut self, context: &mut Context<'_>) -> Poll<Result<Value, EvalError>> {
+        self.result
+            .poll_next_unpin(context)
+            .map(|value| value.ok_or(EvalError::Finished))
+    }
+}
+
+/// Start an eval call from `entity`'s document, sending bevy's end to `evals`.
+pub(crate) fn bevy_eval(entity: Entity, script: String, evals: &Sender<DioxusEval>) -> Eval {
+    let (to_bevy, from_document) = crossbeam_channel::unbounded();
+    let (to_document, from_bevy) = futures_channel::mpsc::unbounded();
+    let (result_tx, result_rx) = futures_channel::mpsc::unbounded();
+    let _ = evals.send(DioxusEval {
+        entity,
+        script,
+        from_document,
+        to_document,
+        result: result_tx,
+    });
+
+    let owner = dioxus_core::current_owner::<UnsyncStorage>();
+    Eval::new(owner.insert(Box::new(BevyEvaluator {
+        to_bevy,
+        from_bevy,
+        result: result_rx,
+    }) as Box<dyn Evaluator>))
+}
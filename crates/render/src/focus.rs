@@ -0,0 +1,259 @@
+//! Spatial focus navigation, for moving through a document without a mouse.
+
+use blitz_dom::{BaseDocument, Document};
+use blitz_traits::events::{
+    BlitzPointerEvent, BlitzPointerId, MouseEventButton, MouseEventButtons, PointerCoords, UiEvent,
+};
+use dioxus_native::DioxusDocument;
+
+/// A gamepad or remote style navigation step, applied to a document's focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FocusNavigation {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Click the focused element.
+    Activate,
+}
+
+/// Center of a laid out node, relative to the viewport.
+fn center(doc: &BaseDocument, node_id: usize) -> Option<(f64, f64)> {
+    let rect = doc.get_client_bounding_rect(node_id)?;
+    (rect.width > 0.0 && rect.height > 0.0)
+        .then(|| (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0))
+}
+
+/// Laid out focusable elements, with their centers.
+fn focusable_centers(doc: &BaseDocument) -> Vec<(usize, (f64, f64))> {
+    doc.tree()
+        .iter()
+        .filter(|(_, node)| node.is_focussable())
+        .filter_map(|(id, _)| Some((id, center(doc, id)?)))
+        .collect()
+}
+
+/// How far `to` is from `from` in `navigation`'s direction, or `None` if it's behind.
+/// Elements off to the side count for more, so navigation prefers staying in line.
+fn distance(from: (f64, f64), to: (f64, f64), navigation: FocusNavigation) -> Option<f64> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (along, across) = match navigation {
+        FocusNavigation::Up => (-dy, dx),
+        FocusNavigation::Down => (dy, dx),
+        FocusNavigation::Left => (-dx, dy),
+        FocusNavigation::Right => (dx, dy),
+        FocusNavigation::Activate => return None,
+    };
+    (along > 0.0).then(|| along + across.abs() * 2.0)
+}
+
+/// How far a span starting at `start` moves to fit inside `0..size`. Spans larger than
+/// `size` keep their start in view.
+fn overflow(start: f64, length: f64, size: f64) -> f64 {
+    if start < 0.0 {
+        start
+    } else if start + length > size {
+        (start + length - size).min(start)
+    } else {
+        0.0
+    }
+}
+
+/// Scroll the nearest edge of a node into the viewport.
+fn scroll_into_view(doc: &mut BaseDocument, node_id: usize) {
+    let Some(rect) = doc.get_client_bounding_rect(node_id) else {
+        return;
+    };
+    let viewport = doc.viewport();
+    let scale = viewport.scale() as f64;
+    let dx = overflow(rect.x, rect.width, viewport.window_size.0 as f64 / scale);
+    let dy = overflow(rect.y, rect.height, viewport.window_size.1 as f64 / scale);
+    if dx == 0.0 && dy == 0.0 {
+        return;
+    }
+    // Scrolls the closest scrollable ancestor first, bubbling up to the viewport
+    let parent = doc.get_node(node_id).and_then(|node| node.parent);
+    doc.scroll_by(parent, -dx, -dy, &mut |_| {});
+}
+
+/// Move focus to the nearest focusable element in a direction, scrolling it into view, or
+/// click the focused element. With nothing focused, a direction focuses the top left
+/// element.
+pub(crate) fn navigate_focus(document: &mut DioxusDocument, navigation: FocusNavigation) {
+    let doc = document.inner.borrow();
+    let focused = doc
+        .get_focussed_node_id()
+        .and_then(|id| Some((id, center(&doc, id)?)));
+
+    if navigation == FocusNavigation::Activate {
+        drop(doc);
+        let Some((_, (x, y))) = focused else {
+            return;
+        };
+        let pointer_event = |buttons| BlitzPointerEvent {
+            id: BlitzPointerId::Mouse,
+            is_primary: true,
+            coords: PointerCoords {
+                page_x: x as f32,
+                page_y: y as f32,
+                screen_x: x as f32,
+                screen_y: y as f32,
+                client_x: x as f32,
+                client_y: y as f32,
+            },
+            button: MouseEventButton::Main,
+            buttons,
+            mods: Default::default(),
+            details: Default::default(),
+        };
+        document.handle_ui_event(UiEvent::PointerDown(pointer_event(
+            MouseEventButtons::Primary,
+        )));
+        document.handle_ui_event(UiEvent::PointerUp(pointer_event(MouseEventButtons::None)));
+        return;
+    }
+
+    let candidates = focusable_centers(&doc);
+    let next = match focused {
+        Some((focused_id, from)) => candidates
+            .iter()
+            .filter(|(id, _)| *id != focused_id)
+            .filter_map(|(id, to)| Some((*id, distance(from, *to, navigation)?)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(id, _)| id),
+        None => candidates
+            .iter()
+            .min_by(|(_, a), (_, b)| (a.0 + a.1).total_cmp(&(b.0 + b.1)))
+            .map(|(id, _)| *id),
+    };
+    drop(doc);
+
+    if let Some(id) = next {
+        let mut doc = document.inner.borrow_mut();
+        doc.set_focus_to(id);
+        scroll_into_view(&mut doc, id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blitz_dom::{DocumentConfig, local_name};
+    use blitz_traits::shell::{ColorScheme, Viewport};
+    use dioxus_core::{Element, VirtualDom};
+    use dioxus_core_macro::rsx;
+
+    use super::*;
+
+    const ORIGIN: (f64, f64) = (0.0, 0.0);
+
+    #[test]
+    fn distance_is_along_the_direction() {
+        use FocusNavigation::*;
+        assert_eq!(distance(ORIGIN, (0.0, 10.0), Down), Some(10.0));
+        assert_eq!(distance(ORIGIN, (0.0, -10.0), Up), Some(10.0));
+        assert_eq!(distance(ORIGIN, (-10.0, 0.0), Left), Some(10.0));
+        assert_eq!(distance(ORIGIN, (10.0, 0.0), Right), Some(10.0));
+    }
+
+    #[test]
+    fn distance_skips_elements_behind_or_level() {
+        use FocusNavigation::*;
+        assert_eq!(distance(ORIGIN, (0.0, 10.0), Up), None);
+        assert_eq!(distance(ORIGIN, (10.0, 0.0), Down), None);
+        assert_eq!(distance(ORIGIN, (10.0, 0.0), Activate), None);
+    }
+
+    #[test]
+    fn distance_prefers_elements_in_line() {
+        let in_line = distance(ORIGIN, (20.0, 0.0), FocusNavigation::Right).unwrap();
+        let diagonal = distance(ORIGIN, (10.0, 10.0), FocusNavigation::Right).unwrap();
+        assert!(in_line < diagonal);
+    }
+
+    #[test]
+    fn overflow_moves_spans_into_view() {
+        assert_eq!(overflow(10.0, 20.0, 100.0), 0.0);
+        assert_eq!(overflow(-5.0, 20.0, 100.0), -5.0);
+        assert_eq!(overflow(90.0, 20.0, 100.0), 10.0);
+        // Too large to fit, so its start is kept in view
+        assert_eq!(overflow(50.0, 200.0, 100.0), 50.0);
+    }
+
+    fn grid() -> Element {
+        rsx! {
+            div {
+                id: "top-left",
+                tabindex: "0",
+                style: "position: absolute; left: 0px; top: 0px; width: 50px; height: 50px;",
+            }
+            div {
+                id: "top-right",
+                tabindex: "0",
+                style: "position: absolute; left: 100px; top: 0px; width: 50px; height: 50px;",
+            }
+            div {
+                id: "bottom-left",
+                tabindex: "0",
+                style: "position: absolute; left: 0px; top: 100px; width: 50px; height: 50px;",
+            }
+            div {
+                id: "bottom-right",
+                tabindex: "0",
+                style: "position: absolute; left: 100px; top: 100px; width: 50px; height: 50px;",
+            }
+        }
+    }
+
+    fn grid_document() -> DioxusDocument {
+        let mut document = DioxusDocument::new(
+            VirtualDom::new(grid),
+            DocumentConfig {
+                ua_stylesheets: Some(vec![blitz_dom::DEFAULT_CSS.to_string()]),
+                ..Default::default()
+            },
+        );
+        document.initial_build();
+        let mut doc = document.inner.borrow_mut();
+        doc.set_viewport(Viewport::new(400, 400, 1.0, ColorScheme::Light));
+        doc.resolve(0.0);
+        drop(doc);
+        document
+    }
+
+    fn focused_id(document: &DioxusDocument) -> Option<String> {
+        let doc = document.inner.borrow();
+        let node = doc.get_node(doc.get_focussed_node_id()?)?;
+        node.attr(local_name!("id")).map(str::to_string)
+    }
+
+    #[test]
+    fn navigate_focus_starts_at_the_top_left() {
+        let mut document = grid_document();
+        navigate_focus(&mut document, FocusNavigation::Down);
+        assert_eq!(focused_id(&document).as_deref(), Some("top-left"));
+    }
+
+    #[test]
+    fn navigate_focus_moves_to_the_nearest_element() {
+        let mut document = grid_document();
+        navigate_focus(&mut document, FocusNavigation::Right);
+        for (navigation, expected) in [
+            (FocusNavigation::Right, "top-right"),
+            (FocusNavigation::Down, "bottom-right"),
+            (FocusNavigation::Left, "bottom-left"),
+            (FocusNavigation::Up, "top-left"),
+        ] {
+            navigate_focus(&mut document, navigation);
+            assert_eq!(focused_id(&document).as_deref(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn navigate_focus_stays_at_the_edge() {
+        let mut document = grid_document();
+        navigate_focus(&mut document, FocusNavigation::Up);
+        navigate_focus(&mut document, FocusNavigation::Up);
+        navigate_focus(&mut document, FocusNavigation::Left);
+        assert_eq!(focused_id(&document).as_deref(), Some("top-left"));
+    }
+}
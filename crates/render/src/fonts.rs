@@ -0,0 +1,271 @@
+//! Fonts loaded through bevy's [`AssetServer`] and registered into every document.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use bevy_asset::{AssetLoader, LoadContext, io::Reader, prelude::*};
+use bevy_dioxus_tracing::{debug, warn};
+use bevy_ecs::prelude::*;
+use bevy_reflect::TypePath;
+use blitz_dom::FontContext;
+use parley::fontique::{
+    Collection, CollectionOptions, FamilyId, FontInfo, GenericFamily, SourceCache,
+};
+use vello::peniko::Blob;
+
+use crate::worker::{VdomCommand, VdomThreadRegistry};
+
+/// Raw `.ttf`/`.otf` font data for dioxus documents.
+///
+/// Load with a typed handle, e.g. `asset_server.load::<DioxusFont>("fonts/brand.ttf")`,
+/// so this loader is picked over any other loader registered for the same extensions.
+/// bevy_text's `Font` loader also claims `ttf`/`otf`, and an untyped load (`load_untyped`,
+/// or a path referenced from a scene) goes to whichever of the two was registered last,
+/// which depends on plugin order.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct DioxusFont {
+    pub data: Blob<u8>,
+}
+
+#[derive(Default, TypePath)]
+pub struct DioxusFontLoader;
+
+impl AssetLoader for DioxusFontLoader {
+    type Asset = DioxusFont;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<DioxusFont, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(DioxusFont {
+            data: Blob::from(bytes),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ttf", "otf"]
+    }
+}
+
+/// A font to register, and the generic families (`sans-serif`, `monospace`, ...) that
+/// should resolve to it.
+#[derive(Clone, Debug)]
+pub struct FontRegistration {
+    pub data: Blob<u8>,
+    pub generic_families: Vec<GenericFamily>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DioxusFontEntry {
+    pub handle: Handle<DioxusFont>,
+    pub generic_families: Vec<GenericFamily>,
+}
+
+/// Fonts registered into the [`SharedFontContext`] used by every document.
+/// Fonts are re-registered when their asset changes, and unregistered when their entry is
+/// removed or their asset unloaded.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct DioxusFonts {
+    pub fonts: Vec<DioxusFontEntry>,
+}
+
+impl DioxusFonts {
+    pub fn add(
+        &mut self,
+        handle: Handle<DioxusFont>,
+        generic_families: impl IntoIterator<Item = GenericFamily>,
+    ) {
+        self.fonts.push(DioxusFontEntry {
+            handle,
+            generic_families: generic_families.into_iter().collect(),
+        });
+    }
+}
+
+/// Fonts one registration added to the collection, by family.
+pub type RegisteredFonts = Vec<(FamilyId, Vec<FontInfo>)>;
+
+/// Register a font, pointing its generic families at the font's first family.
+pub(crate) fn register_font(
+    font_ctx: &mut FontContext,
+    font: &FontRegistration,
+) -> RegisteredFonts {
+    let families = font_ctx.collection.register_fonts(font.data.clone(), None);
+    let Some((family_id, _)) = families.first() else {
+        warn!("font data registered no font families");
+        return families;
+    };
+    for generic in &font.generic_families {
+        font_ctx
+            .collection
+            .set_generic_families(*generic, std::iter::once(*family_id));
+    }
+    families
+}
+
+/// Font collection and source cache shared by every document, so font data is loaded
+/// and parsed once no matter how many ui quads exist.
+#[derive(Resource)]
+pub struct SharedFontContext(Mutex<FontContext>);
+
+impl Default for SharedFontContext {
+    fn default() -> Self {
+        let mut font_ctx = FontContext {
+            collection: Collection::new(CollectionOptions {
+                shared: true,
+                system_fonts: true,
+            }),
+            source_cache: SourceCache::new_shared(),
+        };
+        setup_fallback_font(&mut font_ctx);
+        Self(Mutex::new(font_ctx))
+    }
+}
+
+impl SharedFontContext {
+    /// Font context for a new document. Shares its fonts with every other document.
+    pub(crate) fn document_font_ctx(&self) -> FontContext {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn register(&self, font: &FontRegistration) -> RegisteredFonts {
+        register_font(&mut self.0.lock().unwrap(), font)
+    }
+
+    /// Remove fonts added by [`SharedFontContext::register`].
+    pub fn unregister(&self, fonts: &RegisteredFonts) {
+        let mut font_ctx = self.0.lock().unwrap();
+        for (family_id, family_fonts) in fonts {
+            for font in family_fonts {
+                font_ctx.collection.unregister_font(
+                    *family_id,
+                    font.width(),
+                    font.style(),
+                    font.weight(),
+                );
+            }
+        }
+    }
+}
+
+/// On Linux, when blitz can't find a font, it will silently fail to render text.
+/// This work around forcibly includes the below workaround font.
+///
+/// This work around also points blitz's default stylesheet fonts to this font.
+#[cfg(target_os = "linux")]
+fn setup_fallback_font(font_ctx: &mut FontContext) {
+    use parley::fontique::GenericFamily::*;
+    use std::sync::Arc;
+
+    let font_data: &'static [u8] = include_bytes!("../../../assets/JetBrainsMono-Medium.ttf");
+    register_font(
+        font_ctx,
+        &FontRegistration {
+            data: Blob::new(Arc::new(font_data)),
+            generic_families: vec![Serif, SansSerif, Monospace, Cursive, Fantasy, SystemUi],
+        },
+    );
+}
+
+#[cfg(not(target_os = "linux"))]
+fn setup_fallback_font(_font_ctx: &mut FontContext) {}
+
+/// What [`sync_dioxus_fonts`] registered for one font asset.
+pub(crate) struct RegisteredFont {
+    /// Generic families of every entry using the asset, to notice entries changing.
+    generic_families: Vec<Vec<GenericFamily>>,
+    fonts: RegisteredFonts,
+}
+
+/// Generic families of every [`DioxusFonts`] entry using `id`, in order.
+fn entry_families(fonts: &DioxusFonts, id: AssetId<DioxusFont>) -> Vec<Vec<GenericFamily>> {
+    fonts
+        .fonts
+        .iter()
+        .filter(|entry| entry.handle.id() == id)
+        .map(|entry| entry.generic_families.clone())
+        .collect()
+}
+
+/// Registers fonts that finished loading or changed into the shared font context and
+/// tells every running worker to re-layout its text. A changed font replaces the fonts it
+/// registered before, rather than adding to its family, and fonts whose asset was unloaded
+/// or whose [`DioxusFonts`] entry was removed are unregistered.
+///
+/// Reads asset events, so it must run every frame rather than in the fps capped schedule.
+pub(crate) fn sync_dioxus_fonts(
+    mut events: MessageReader<AssetEvent<DioxusFont>>,
+    fonts: Res<DioxusFonts>,
+    assets: Res<Assets<DioxusFont>>,
+    shared_fonts: Res<SharedFontContext>,
+    registry: NonSend<VdomThreadRegistry>,
+    mut registered: Local<HashMap<AssetId<DioxusFont>, RegisteredFont>>,
+) {
+    let mut stale = HashSet::new();
+    for event in events.read() {
+        match event {
+            AssetEvent::LoadedWithDependencies { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::Removed { id }
+            | AssetEvent::Unused { id } => {
+                stale.insert(*id);
+            }
+            AssetEvent::Added { .. } => {}
+        }
+    }
+    // Entries added, removed or edited since the last sync
+    if fonts.is_changed() {
+        let ids = fonts.fonts.iter().map(|entry| entry.handle.id());
+        for id in ids.chain(registered.keys().copied()) {
+            let families = entry_families(&fonts, id);
+            let current = registered.get(&id).map(|font| &font.generic_families);
+            if current != Some(&families) {
+                stale.insert(id);
+            }
+        }
+    }
+
+    let mut changed = false;
+    for id in stale {
+        // Unregister once, so entries sharing a handle don't remove each other's fonts
+        if let Some(previous) = registered.remove(&id) {
+            shared_fonts.unregister(&previous.fonts);
+            changed = true;
+        }
+        let generic_families = entry_families(&fonts, id);
+        let Some(font) = assets.get(id) else {
+            continue;
+        };
+        if generic_families.is_empty() {
+            continue;
+        }
+        let mut added = RegisteredFonts::new();
+        for families in &generic_families {
+            added.extend(shared_fonts.register(&FontRegistration {
+                data: font.data.clone(),
+                generic_families: families.clone(),
+            }));
+        }
+        registered.insert(
+            id,
+            RegisteredFont {
+                generic_families,
+                fonts: added,
+            },
+        );
+        changed = true;
+        debug!("registered font {} with all documents", id);
+    }
+
+    if changed {
+        for worker in registry.workers.values() {
+            let _ = worker.cmd_tx.try_send(VdomCommand::FontsChanged);
+        }
+    }
+}
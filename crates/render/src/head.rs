@@ -0,0 +1,202 @@
+//! Document `<head>` state mirrored into bevy: the title and favicon.
+//!
+//! Every ui quad gets a [`DioxusDocumentTitle`] and [`DioxusDocumentIcon`] once its document
+//! sets them. The window ui quad's title is also shown as the window title, and with the
+//! `window_icon` feature its icon as the window icon.
+
+use bevy_ecs::prelude::*;
+use bevy_window::{PrimaryWindow, Window};
+use crossbeam_channel::Sender;
+
+use crate::{Channel, DioxusWindowUiQuad};
+
+pub(crate) enum HeadUpdate {
+    Title(String),
+    /// Href of a `link rel="icon"`.
+    Icon(String),
+    /// A favicon loaded and decoded for the window icon.
+    #[cfg(feature = "window_icon")]
+    IconImage {
+        href: String,
+        image: bevy_image::Image,
+    },
+}
+
+pub(crate) type DocumentHeadChannel = Channel<(Entity, HeadUpdate)>;
+
+/// Title the ui quad's document set with `document::Title`.
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct DioxusDocumentTitle(pub String);
+
+/// Href of the ui quad's document's favicon, set with `document::Link { rel: "icon" }`.
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct DioxusDocumentIcon(pub String);
+
+pub(crate) fn sync_document_heads(
+    channel: Res<DocumentHeadChannel>,
+    window_quads: Query<(), With<DioxusWindowUiQuad>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    #[cfg(feature = "window_icon")] mut window_icon: ResMut<WindowIconState>,
+    mut commands: Commands,
+) {
+    for (entity, update) in channel.receiver.try_iter() {
+        let Ok(mut entity_commands) = commands.get_entity(entity) else {
+            continue;
+        };
+        match update {
+            HeadUpdate::Title(title) => {
+                if window_quads.contains(entity)
+                    && let Ok(mut window) = windows.single_mut()
+                {
+                    window.title = title.clone();
+                }
+                entity_commands.try_insert(DioxusDocumentTitle(title));
+            }
+            HeadUpdate::Icon(href) => {
+                #[cfg(feature = "window_icon")]
+                if window_quads.contains(entity) {
+                    window_icon.href = Some(href.clone());
+                }
+                entity_commands.try_insert(DioxusDocumentIcon(href));
+            }
+            // Only the latest icon, in case an earlier one finished loading after it
+            #[cfg(feature = "window_icon")]
+            HeadUpdate::IconImage { href, image } => {
+                if window_quads.contains(entity) && window_icon.href.as_ref() == Some(&href) {
+                    window_icon.pending = Some(image);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "window_icon")]
+pub(crate) use window_icon::{WindowIconState, fetch_icon, sync_window_icon};
+
+#[cfg(feature = "window_icon")]
+mod window_icon {
+    use std::sync::Arc;
+
+    use bevy_asset::RenderAssetUsages;
+    use bevy_dioxus_tracing::warn;
+    use bevy_ecs::prelude::*;
+    use bevy_ecs::system::NonSendMarker;
+    use bevy_image::{CompressedImageFormats, Image, ImageSampler, ImageType};
+    use bevy_tasks::IoTaskPool;
+    use bevy_window::PrimaryWindow;
+    use bevy_winit::WINIT_WINDOWS;
+    use blitz_traits::net::{NetHandler, NetProvider, Request, Url};
+    use bytes::Bytes;
+    use crossbeam_channel::Sender;
+    use wgpu::TextureFormat;
+
+    use super::HeadUpdate;
+
+    /// The window ui quad's icon, waiting to be set on the window.
+    #[derive(Resource, Default)]
+    pub(crate) struct WindowIconState {
+        /// Href of the window ui's latest icon.
+        pub(super) href: Option<String>,
+        pub(super) pending: Option<Image>,
+    }
+
+    /// Decodes a loaded icon off the main thread.
+    struct IconHandler {
+        entity: Entity,
+        href: String,
+        head: Sender<(Entity, HeadUpdate)>,
+    }
+
+    impl NetHandler for IconHandler {
+        fn bytes(self: Box<Self>, resolved_url: String, bytes: Bytes) {
+            // Failed loads complete empty, and are already reported
+            if bytes.is_empty() {
+                return;
+            }
+            let IconHandler { entity, href, head } = *self;
+            IoTaskPool::get()
+                .spawn(async move {
+                    let extension = Url::parse(&resolved_url)
+                        .ok()
+                        .and_then(|url| Some(url.path().rsplit_once('.')?.1.to_string()))
+                        .unwrap_or_else(|| "png".to_string());
+                    match Image::from_buffer(
+                        &bytes,
+                        ImageType::Extension(&extension),
+                        CompressedImageFormats::NONE,
+                        true,
+                        ImageSampler::Default,
+                        RenderAssetUsages::MAIN_WORLD,
+                    ) {
+                        Ok(image) => {
+                            let _ = head.send((entity, HeadUpdate::IconImage { href, image }));
+                        }
+                        Err(_err) => {
+                            warn!("failed to decode window icon {}: {}", href, _err);
+                        }
+                    }
+                })
+                .detach();
+        }
+    }
+
+    /// Base url dioxus-native documents get when their quad has no `DioxusBaseUrl`.
+    const DEFAULT_BASE_URL: &str = "dioxus://index.html/";
+
+    /// Load a document's icon the way the document loads its other resources, for use as
+    /// the window icon.
+    pub(crate) fn fetch_icon(
+        net_provider: &Arc<dyn NetProvider>,
+        base_url: Option<&Url>,
+        entity: Entity,
+        href: String,
+        head: Sender<(Entity, HeadUpdate)>,
+    ) {
+        let url = match base_url {
+            Some(base_url) => base_url.join(&href),
+            None => Url::parse(DEFAULT_BASE_URL).and_then(|base_url| base_url.join(&href)),
+        };
+        let Ok(url) = url else {
+            warn!("invalid window icon url {}", href);
+            return;
+        };
+        net_provider.fetch(
+            0,
+            Request::get(url),
+            Box::new(IconHandler { entity, href, head }),
+        );
+    }
+
+    pub(crate) fn sync_window_icon(
+        windows: Query<Entity, With<PrimaryWindow>>,
+        mut state: ResMut<WindowIconState>,
+        _non_send: NonSendMarker,
+    ) {
+        let Some(image) = state.pending.take() else {
+            return;
+        };
+        let size = image.size();
+        let Some(rgba) = image
+            .convert(TextureFormat::Rgba8UnormSrgb)
+            .and_then(|image| image.data)
+        else {
+            warn!("window icon has an unsupported format");
+            return;
+        };
+        let icon = match winit::window::Icon::from_rgba(rgba, size.x, size.y) {
+            Ok(icon) => icon,
+            Err(_err) => {
+                warn!("invalid window icon: {}", _err);
+                return;
+            }
+        };
+        let Ok(window) = windows.single() else {
+            return;
+        };
+        WINIT_WINDOWS.with_borrow(|winit_windows| {
+            if let Some(winit_window) = winit_windows.get_window(window) {
+                winit_window.set_window_icon(Some(icon));
+            }
+        });
+    }
+}
@@ -0,0 +1,387 @@
+//! `http://` and `https://` requests made by documents.
+//!
+//! Requests go through a replaceable [`DioxusHttpClient`] and run on the [`IoTaskPool`], so
+//! they never block a document's worker thread. Insert a [`DioxusHttp`] resource to enable
+//! them. With the `http` feature, [`DioxusHttp::default`] uses a [`UreqHttpClient`].
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy_ecs::prelude::*;
+use bevy_tasks::{BoxedFuture, IoTaskPool, Task};
+use blitz_traits::net::Request;
+#[cfg(feature = "http")]
+use blitz_traits::net::{Body, EntryValue, FormData, Url, http::header::CONTENT_TYPE};
+use bytes::Bytes;
+
+use crate::net_provider::FetchResponder;
+
+pub type HttpError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Performs http requests for documents. Replace it to serve requests from somewhere else,
+/// e.g. a local stand-in server in tests.
+pub trait DioxusHttpClient: Send + Sync + 'static {
+    /// Fetch the body of `request`, failing if it takes longer than `timeout`.
+    fn fetch(
+        &self,
+        request: Request,
+        timeout: Duration,
+    ) -> BoxedFuture<'static, Result<Bytes, HttpError>>;
+}
+
+/// Enables http requests in documents created after it is inserted.
+#[derive(Resource, Clone)]
+pub struct DioxusHttp {
+    pub client: Arc<dyn DioxusHttpClient>,
+    /// Most requests in flight at once, across every document. More requests wait their turn.
+    pub max_concurrent: usize,
+    pub timeout: Duration,
+}
+
+impl DioxusHttp {
+    pub fn new(client: impl DioxusHttpClient) -> Self {
+        Self {
+            client: Arc::new(client),
+            max_concurrent: 6,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[cfg(feature = "http")]
+impl Default for DioxusHttp {
+    fn default() -> Self {
+        Self::new(UreqHttpClient::default())
+    }
+}
+
+/// Blocking [`ureq`] client. Each request runs on its own thread, so it blocks neither the
+/// document nor bevy's io task pool.
+///
+/// Cancellation is best-effort: a cancelled request's thread keeps running until the request
+/// finishes or times out, it just no longer holds one of [`DioxusHttp::max_concurrent`]'s
+/// slots. At most `max_concurrent` threads are busy with wanted requests, plus however many
+/// cancelled ones haven't hit [`DioxusHttp::timeout`] yet.
+#[cfg(feature = "http")]
+#[derive(Default)]
+pub struct UreqHttpClient {
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "http")]
+impl DioxusHttpClient for UreqHttpClient {
+    fn fetch(
+        &self,
+        request: Request,
+        timeout: Duration,
+    ) -> BoxedFuture<'static, Result<Bytes, HttpError>> {
+        let agent = self.agent.clone();
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        let run = move || -> Result<Bytes, HttpError> {
+            let mut builder = ureq::http::Request::builder()
+                .method(request.method.as_str())
+                .uri(request.url.as_str());
+            for (name, value) in request.headers.iter() {
+                builder = builder.header(name.as_str(), value.as_bytes());
+            }
+            let (body, default_content_type) = match &request.body {
+                Body::Bytes(bytes) => (Some(bytes.to_vec()), None),
+                Body::Form(form) => (
+                    Some(encode_form(form).into_bytes()),
+                    Some("application/x-www-form-urlencoded"),
+                ),
+                Body::Empty => (None, None),
+            };
+            if !request.headers.contains_key(CONTENT_TYPE)
+                && let Some(content_type) = request.content_type.as_deref().or(default_content_type)
+            {
+                builder = builder.header(CONTENT_TYPE.as_str(), content_type);
+            }
+            // Without a body when there is none, since ureq rejects bodies on methods like GET
+            let response = match body {
+                Some(body) => agent.run(
+                    agent
+                        .configure_request(builder.body(body)?)
+                        .timeout_global(Some(timeout))
+                        .build(),
+                ),
+                None => agent.run(
+                    agent
+                        .configure_request(builder.body(())?)
+                        .timeout_global(Some(timeout))
+                        .build(),
+                ),
+            }?;
+            Ok(Bytes::from(response.into_body().read_to_vec()?))
+        };
+        // A cancelled request leaves its thread to finish or time out on its own
+        let spawned = std::thread::Builder::new()
+            .name("dioxus-http".to_string())
+            .spawn(move || {
+                let _ = sender.send(run());
+            });
+        Box::pin(async move {
+            spawned?;
+            receiver.await?
+        })
+    }
+}
+
+/// Encode `form` as `application/x-www-form-urlencoded`, the way a browser submits it.
+#[cfg(feature = "http")]
+fn encode_form(form: &FormData) -> String {
+    // Borrows `Url`'s form encoder, which blitz doesn't re-export on its own
+    let mut encoded = Url::parse("data:,").expect("valid url");
+    encoded
+        .query_pairs_mut()
+        .extend_pairs(form.iter().map(|entry| {
+            let value = match &entry.value {
+                EntryValue::String(value) => value.as_str(),
+                // Browsers send only the file's name in url encoded forms
+                EntryValue::File(path) => path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default(),
+                EntryValue::EmptyFile => "",
+            };
+            (entry.name.as_str(), value)
+        }));
+    encoded.query().unwrap_or_default().to_string()
+}
+
+struct HttpJob {
+    request: Request,
+    responder: FetchResponder,
+}
+
+/// A running request. Dropping it cancels the request.
+struct ActiveRequest {
+    task: Task<()>,
+    responder: FetchResponder,
+}
+
+#[derive(Default)]
+struct HttpQueueState {
+    next_id: u64,
+    running: HashMap<u64, ActiveRequest>,
+    queued: VecDeque<HttpJob>,
+}
+
+/// Requests from every document, started in order up to [`DioxusHttp::max_concurrent`] at once.
+pub(crate) struct HttpQueue {
+    http: DioxusHttp,
+    state: Mutex<HttpQueueState>,
+}
+
+impl HttpQueue {
+    pub(crate) fn new(http: DioxusHttp) -> Arc<Self> {
+        Arc::new(Self {
+            http,
+            state: Mutex::new(HttpQueueState::default()),
+        })
+    }
+
+    pub(crate) fn push(self: &Arc<Self>, request: Request, responder: FetchResponder) {
+        self.state
+            .lock()
+            .unwrap()
+            .queued
+            .push_back(HttpJob { request, responder });
+        self.start_queued();
+    }
+
+    fn start_queued(self: &Arc<Self>) {
+        // Dropped after unlocking, since dropping a shared load can restart it
+        let mut unwanted = Vec::new();
+        let mut state = self.state.lock().unwrap();
+        while state.running.len() < self.http.max_concurrent.max(1) {
+            let Some(HttpJob { request, responder }) = state.queued.pop_front() else {
+                break;
+            };
+            // Every document that wanted it went away while the request waited
+            if !responder.is_wanted() {
+                unwanted.push(responder);
+                continue;
+            }
+            let id = state.next_id;
+            state.next_id += 1;
+
+            let queue = Arc::downgrade(self);
+            let fetch = self.http.client.fetch(request, self.http.timeout);
+            // Spawned while holding the lock, so the task can't finish before it is recorded
+            let task = IoTaskPool::get().spawn(async move {
+                let result = fetch.await;
+                if let Some(queue) = queue.upgrade() {
+                    queue.finish(id, result);
+                }
+            });
+            state.running.insert(id, ActiveRequest { task, responder });
+        }
+        drop(state);
+        drop(unwanted);
+    }
+
+    /// Complete request `id`, freeing its slot for the next queued request.
+    fn finish(self: &Arc<Self>, id: u64, result: Result<Bytes, HttpError>) {
+        let active = self.state.lock().unwrap().running.remove(&id);
+        if let Some(ActiveRequest { task, responder }) = active {
+            // Called from inside the task, which must not cancel itself
+            task.detach();
+            if responder.is_wanted() {
+                match result {
+                    Ok(bytes) => responder.respond(bytes),
+                    Err(err) => responder.fail(err),
+                }
+            }
+        }
+        self.start_queued();
+    }
+
+    /// Cancel running requests no document wants anymore, freeing their slots.
+    pub(crate) fn cancel_unwanted(self: &Arc<Self>) {
+        let cancelled: Vec<ActiveRequest> = {
+            let mut state = self.state.lock().unwrap();
+            let unwanted: Vec<u64> = state
+                .running
+                .iter()
+                .filter(|(_, active)| !active.responder.is_wanted())
+                .map(|(id, _)| *id)
+                .collect();
+            unwanted
+                .iter()
+                .filter_map(|id| state.running.remove(id))
+                .collect()
+        };
+        if !cancelled.is_empty() {
+            drop(cancelled);
+            self.start_queued();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_tasks::TaskPool;
+    use blitz_traits::net::{NetHandler, Url};
+
+    use super::*;
+    use crate::net_provider::LoadFailures;
+
+    /// Records requested urls and never completes them, leaving the test to `finish` them.
+    #[derive(Clone, Default)]
+    struct StubClient(Arc<Mutex<Vec<String>>>);
+
+    impl StubClient {
+        fn fetched(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl DioxusHttpClient for StubClient {
+        fn fetch(
+            &self,
+            request: Request,
+            _timeout: Duration,
+        ) -> BoxedFuture<'static, Result<Bytes, HttpError>> {
+            self.0.lock().unwrap().push(request.url.to_string());
+            Box::pin(std::future::pending())
+        }
+    }
+
+    /// Bodies delivered to one request.
+    #[derive(Clone, Default)]
+    struct Received(Arc<Mutex<Vec<Bytes>>>);
+
+    impl NetHandler for Received {
+        fn bytes(self: Box<Self>, _resolved_url: String, bytes: Bytes) {
+            self.0.lock().unwrap().push(bytes);
+        }
+    }
+
+    fn queue(max_concurrent: usize) -> (Arc<HttpQueue>, StubClient) {
+        IoTaskPool::get_or_init(TaskPool::new);
+        let client = StubClient::default();
+        let http = DioxusHttp::new(client.clone()).with_max_concurrent(max_concurrent);
+        (HttpQueue::new(http), client)
+    }
+
+    /// Queue a request for `url` from `document`, returning what it receives.
+    fn push(queue: &Arc<HttpQueue>, url: &str, document: &Arc<()>) -> Received {
+        let received = Received::default();
+        let (sender, _) = crossbeam_channel::unbounded();
+        let responder = FetchResponder::new(
+            url.to_string(),
+            Box::new(received.clone()),
+            LoadFailures::new(Entity::PLACEHOLDER, sender),
+            Arc::downgrade(document),
+        );
+        queue.push(Request::get(Url::parse(url).unwrap()), responder);
+        received
+    }
+
+    fn counts(queue: &HttpQueue) -> (usize, usize) {
+        let state = queue.state.lock().unwrap();
+        (state.running.len(), state.queued.len())
+    }
+
+    #[test]
+    fn runs_at_most_max_concurrent() {
+        let (queue, client) = queue(2);
+        let document = Arc::new(());
+        let first = push(&queue, "http://a.test/1", &document);
+        push(&queue, "http://a.test/2", &document);
+        push(&queue, "http://a.test/3", &document);
+        assert_eq!(client.fetched(), ["http://a.test/1", "http://a.test/2"]);
+        assert_eq!(counts(&queue), (2, 1));
+
+        queue.finish(0, Ok(Bytes::from_static(b"one")));
+        assert_eq!(*first.0.lock().unwrap(), [Bytes::from_static(b"one")]);
+        assert_eq!(client.fetched().len(), 3);
+        assert_eq!(counts(&queue), (2, 0));
+    }
+
+    #[test]
+    fn queued_requests_no_document_wants_are_skipped() {
+        let (queue, client) = queue(1);
+        let document = Arc::new(());
+        let closed = Arc::new(());
+        push(&queue, "http://a.test/1", &document);
+        push(&queue, "http://a.test/2", &closed);
+        push(&queue, "http://a.test/3", &document);
+        drop(closed);
+
+        queue.finish(0, Ok(Bytes::new()));
+        assert_eq!(client.fetched(), ["http://a.test/1", "http://a.test/3"]);
+        assert_eq!(counts(&queue), (1, 0));
+    }
+
+    #[test]
+    fn cancelling_unwanted_requests_frees_their_slots() {
+        let (queue, client) = queue(1);
+        let closed = Arc::new(());
+        let document = Arc::new(());
+        push(&queue, "http://a.test/1", &closed);
+        push(&queue, "http://a.test/2", &document);
+        assert_eq!(counts(&queue), (1, 1));
+
+        // Still wanted, so nothing is cancelled
+        queue.cancel_unwanted();
+        assert_eq!(client.fetched(), ["http://a.test/1"]);
+
+        drop(closed);
+        queue.cancel_unwanted();
+        assert_eq!(client.fetched(), ["http://a.test/1", "http://a.test/2"]);
+        assert_eq!(counts(&queue), (1, 0));
+    }
+}
@@ -0,0 +1,153 @@
+//! Layout of panels on a dioxus ui quad surface.
+
+/// Region of the surface a panel is laid out in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PanelAnchor {
+    /// Docked to the left edge, spanning the height between top and bottom panels.
+    Left,
+    /// Docked to the right edge, spanning the height between top and bottom panels.
+    Right,
+    /// Docked to the top edge, spanning the full width of the surface.
+    Top,
+    /// Docked to the bottom edge, spanning the full width of the surface.
+    Bottom,
+    /// Fills the area left over by the docked panels.
+    #[default]
+    Center,
+    /// Not docked. Placed at the top left of the surface and sized by its own content,
+    /// so the panel is free to position itself.
+    Floating,
+}
+
+/// Thickness of a docked panel, measured away from the edge it is anchored to.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PanelSize {
+    /// Sized by the panel's content.
+    #[default]
+    Auto,
+    /// Fixed size in CSS pixels.
+    Px(f32),
+    /// Percentage of the surface width (left/right) or height (top/bottom).
+    Percent(f32),
+}
+
+impl PanelSize {
+    fn to_css(self) -> String {
+        match self {
+            PanelSize::Auto => "auto".to_string(),
+            PanelSize::Px(px) => format!("{px}px"),
+            PanelSize::Percent(percent) => format!("{percent}%"),
+        }
+    }
+}
+
+/// Where and how a panel is placed on its surface.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PanelLayout {
+    pub anchor: PanelAnchor,
+    /// Thickness of the panel. Ignored for [`PanelAnchor::Center`] and [`PanelAnchor::Floating`].
+    pub size: PanelSize,
+    /// Stacking order. Panels with a higher z-order are drawn on top.
+    pub z_index: i32,
+}
+
+impl PanelLayout {
+    pub fn new(anchor: PanelAnchor) -> Self {
+        Self {
+            anchor,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_size(mut self, size: PanelSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Inline style for the element wrapping this panel in [`crate::dioxus_ui`].
+    pub(crate) fn wrapper_style(&self) -> String {
+        let size = self.size.to_css();
+        let z_index = self.z_index;
+        match self.anchor {
+            PanelAnchor::Left | PanelAnchor::Right => format!(
+                "position: relative; flex: 0 0 {size}; width: {size}; height: 100%; z-index: {z_index};"
+            ),
+            PanelAnchor::Top | PanelAnchor::Bottom => format!(
+                "position: relative; flex: 0 0 {size}; width: 100%; height: {size}; z-index: {z_index};"
+            ),
+            PanelAnchor::Center => format!(
+                "position: absolute; left: 0; top: 0; width: 100%; height: 100%; z-index: {z_index};"
+            ),
+            PanelAnchor::Floating => {
+                format!("position: absolute; left: 0; top: 0; z-index: {z_index};")
+            }
+        }
+    }
+}
+
+/// Style of the root element all panels are composed inside of.
+pub(crate) const ROOT_STYLE: &str =
+    "position: relative; display: flex; flex-direction: column; width: 100%; height: 100%;";
+
+/// Style of the row holding left, center, and right panels.
+pub(crate) const MIDDLE_ROW_STYLE: &str =
+    "position: relative; display: flex; flex-direction: row; flex: 1 1 auto; min-height: 0; width: 100%;";
+
+/// Style of the region center panels are layered in.
+pub(crate) const CENTER_STYLE: &str = "position: relative; flex: 1 1 auto; min-width: 0; height: 100%;";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_panels_take_their_width_and_full_height() {
+        let layout = PanelLayout::new(PanelAnchor::Left).with_size(PanelSize::Px(200.0));
+        assert_eq!(
+            layout.wrapper_style(),
+            "position: relative; flex: 0 0 200px; width: 200px; height: 100%; z-index: 0;"
+        );
+    }
+
+    #[test]
+    fn edge_panels_take_their_height_and_full_width() {
+        let layout = PanelLayout::new(PanelAnchor::Bottom)
+            .with_size(PanelSize::Percent(25.0))
+            .with_z_index(2);
+        assert_eq!(
+            layout.wrapper_style(),
+            "position: relative; flex: 0 0 25%; width: 100%; height: 25%; z-index: 2;"
+        );
+    }
+
+    #[test]
+    fn auto_size_is_sized_by_content() {
+        let layout = PanelLayout::new(PanelAnchor::Top);
+        assert_eq!(
+            layout.wrapper_style(),
+            "position: relative; flex: 0 0 auto; width: 100%; height: auto; z-index: 0;"
+        );
+    }
+
+    #[test]
+    fn center_and_floating_panels_ignore_size() {
+        let center = PanelLayout::new(PanelAnchor::Center)
+            .with_size(PanelSize::Px(10.0))
+            .with_z_index(-1);
+        assert_eq!(
+            center.wrapper_style(),
+            "position: absolute; left: 0; top: 0; width: 100%; height: 100%; z-index: -1;"
+        );
+
+        let floating = PanelLayout::new(PanelAnchor::Floating).with_size(PanelSize::Px(10.0));
+        assert_eq!(
+            floating.wrapper_style(),
+            "position: absolute; left: 0; top: 0; z-index: 0;"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 use bevy_asset::{RenderAssetUsages, prelude::*};
@@ -33,7 +33,9 @@ use dioxus_signals::{ReadableExt, WritableExt};
 use vello::{RenderParams, Renderer as VelloRenderer, peniko::color::AlphaColor};
 use wgpu::{Extent3d, TextureDimension, TextureFormat};
 
-use crate::panels::{DioxusPanels, DioxusPanelsReceiver};
+use crate::layout::{CENTER_STYLE, MIDDLE_ROW_STYLE, PanelAnchor, ROOT_STYLE};
+use crate::live_image::{LiveImageRequests, LiveImages, LiveTextureReceiver};
+use crate::panels::{DioxusPanels, DioxusPanelsReceiver, DioxusPanelsSender, PanelSpec};
 use crate::worker::{VdomCommand, VdomResult, VdomThreadRegistry};
 
 pub const SCALE_FACTOR: f32 = 1.0;
@@ -85,19 +87,65 @@ pub struct DioxusUiPickState {
     pub active: DioxusUiPickFilter,
 }
 
-pub(crate) mod net_provider;
+/// A document's hit-test of a pointer or touch event sent to it, reported once its worker
+/// handled the event.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct DioxusUiHitTest {
+    /// Ui quad whose document was hit-tested.
+    pub entity: Entity,
+    /// Whether an element that catches events was under the pointer.
+    pub caught: bool,
+}
+
+/// Unbounded channel from document threads to a system that drains it each frame.
+#[derive(Resource)]
+pub(crate) struct Channel<T: Send + Sync + 'static> {
+    pub sender: Sender<T>,
+    receiver: Receiver<T>,
+}
+
+impl<T: Send + Sync + 'static> Default for Channel<T> {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        Self { sender, receiver }
+    }
+}
+
+pub mod cache;
+pub(crate) mod devtools;
+pub mod eval;
+pub mod focus;
+pub mod fonts;
+pub mod head;
+pub mod http;
+pub mod layout;
+pub mod live_image;
+pub mod net_provider;
+pub mod owner;
 pub mod panels;
 pub mod plugins;
 pub(crate) mod schedule;
+pub mod schemes;
 pub mod worker;
 
 /// Extraction-side mirror of texture handles, keyed by the quad entity.
 #[derive(Resource, Default)]
 struct ExtractedTextureImages(pub HashMap<Entity, Handle<Image>>);
 
-/// root ui that all dioxus panels render inside of
+/// default root ui that all dioxus panels render inside of
 #[component]
 pub fn dioxus_ui() -> Element {
+    rsx! {
+        PanelsOutlet {}
+    }
+}
+
+/// Lays out the panels of the surface this document renders on.
+///
+/// Custom roots set with [`DioxusRoot`] or [`plugins::DioxusRenderPlugin::default_root`]
+/// place this where their panels should appear.
+#[component]
+pub fn PanelsOutlet() -> Element {
     let panel_receiver = use_context::<DioxusPanelsReceiver>();
     let mut panels = use_signal(|| DioxusPanels::default());
 
@@ -124,13 +172,93 @@ pub fn dioxus_ui() -> Element {
             }
         }
     });
+
+    let panels = panels.read().cloned();
+    let top = panels.anchored(PanelAnchor::Top);
+    let left = panels.anchored(PanelAnchor::Left);
+    let center = panels.anchored(PanelAnchor::Center);
+    let right = panels.anchored(PanelAnchor::Right);
+    let bottom = panels.anchored(PanelAnchor::Bottom);
+    let floating = panels.anchored(PanelAnchor::Floating);
+
     rsx! {
-        for panel in panels.read().cloned().panels {
-            {panel()}
+        div {
+            style: ROOT_STYLE,
+            for panel in top {
+                div {
+                    key: "{panel.dom_key()}",
+                    style: panel.wrapper_style(),
+                    PanelHost { spec: panel.clone() }
+                }
+            }
+            div {
+                style: MIDDLE_ROW_STYLE,
+                for panel in left {
+                    div {
+                        key: "{panel.dom_key()}",
+                        style: panel.wrapper_style(),
+                        PanelHost { spec: panel.clone() }
+                    }
+                }
+                div {
+                    style: CENTER_STYLE,
+                    for panel in center {
+                        div {
+                            key: "{panel.dom_key()}",
+                            style: panel.wrapper_style(),
+                            PanelHost { spec: panel.clone() }
+                        }
+                    }
+                }
+                for panel in right {
+                    div {
+                        key: "{panel.dom_key()}",
+                        style: panel.wrapper_style(),
+                        PanelHost { spec: panel.clone() }
+                    }
+                }
+            }
+            for panel in bottom {
+                div {
+                    key: "{panel.dom_key()}",
+                    style: panel.wrapper_style(),
+                    PanelHost { spec: panel.clone() }
+                }
+            }
+            for panel in floating {
+                div {
+                    key: "{panel.dom_key()}",
+                    style: panel.wrapper_style(),
+                    PanelHost { spec: panel.clone() }
+                }
+            }
         }
     }
 }
 
+/// Gives each panel its own component scope, so its hooks are isolated from other panels.
+#[component]
+fn PanelHost(spec: PanelSpec) -> Element {
+    (spec.render)()
+}
+
+/// Root component of the document rendered on this ui quad, in place of [`dioxus_ui`].
+///
+/// Use it to wrap panels in providers, error boundaries, routers, or global styles.
+/// Read when the quad's document is created.
+#[derive(Component, Clone, Copy)]
+pub struct DioxusRoot(pub fn() -> Element);
+
+/// Root component for ui quads without a [`DioxusRoot`].
+#[derive(Resource, Clone, Copy)]
+pub struct DefaultDioxusRoot(pub fn() -> Element);
+
+impl Default for DefaultDioxusRoot {
+    fn default() -> Self {
+        Self(dioxus_ui)
+    }
+}
+
 /// Marks camera as dioxus window ui camera
 ///
 /// TODO: Add multi-window support
@@ -166,10 +294,11 @@ fn recompute_dioxus_ui_quad_surface(
         &Mesh3d,
         &mut DioxusUiQuad,
         Option<&DioxusUiResolution>,
+        Has<DioxusUiMirror>,
     )>,
     meshes: Res<Assets<Mesh>>,
 ) {
-    for (_e, surface, mut ui, resolution) in &mut surfaces {
+    for (_e, surface, mut ui, resolution, is_mirror) in &mut surfaces {
         let id = surface.id();
         let Some(surface) = meshes.get(id) else {
             warn!("surface id not valid for? {}", id);
@@ -238,10 +367,15 @@ fn recompute_dioxus_ui_quad_surface(
                 (y_max - y_min) * RESOLUTION_SCALE,
             )
         };
-        let new_wh = Some(Vec2 {
-            x: width,
-            y: height,
-        });
+        // Mirrors render at their source's resolution, see `sync_mirror_surfaces`.
+        let new_wh = if is_mirror {
+            ui.computed_wh
+        } else {
+            Some(Vec2 {
+                x: width,
+                y: height,
+            })
+        };
 
         // Only change the quad if the underlying value actually changed
         if ui.computed_wh == new_wh && ui.local_half_extents == half_extents {
@@ -304,7 +438,7 @@ pub fn create_ui_texture(width: u32, height: u32) -> Image {
 
 fn extract_texture_images(
     mut commands: Commands,
-    quad_query: Extract<Query<(Entity, &DioxusUiQuad)>>,
+    quad_query: Extract<Query<(Entity, &DioxusUiQuad), Without<DioxusUiMirror>>>,
     mut last_handles: Local<HashMap<Entity, Handle<Image>>>,
     extracted_images: Option<Res<ExtractedTextureImages>>,
 ) {
@@ -390,10 +524,15 @@ fn collect_and_render_vdom_scenes(
     images: Res<Assets<Image>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut quad_query: Query<(Entity, &mut MeshMaterial3d<StandardMaterial>, &DioxusUiQuad)>,
-    window_uis: Query<Entity, With<DioxusWindowUiQuad>>,
-    world_space_uis: Query<Entity, (With<DioxusUiQuad>, Without<DioxusWindowUiQuad>)>,
+    // Whether each ui quad is the window ui, rather than a world-space quad
+    ui_spaces: Query<Has<DioxusWindowUiQuad>, With<DioxusUiQuad>>,
     mut cached_textures: Local<HashMap<Entity, RenderTexture>>,
     mut pick_state: ResMut<DioxusUiPickState>,
+    mut hit_tests: MessageWriter<DioxusUiHitTest>,
+    live_texture_receiver: Res<LiveTextureReceiver>,
+    mut live_images: Local<LiveImages>,
+    mut live_image_requests: ResMut<LiveImageRequests>,
+    mut panel_channels: Query<(&mut DioxusPanels, &mut DioxusPanelsSender)>,
 ) {
     let _ = debug_span!("total vdom(s) render time").entered();
 
@@ -421,6 +560,8 @@ fn collect_and_render_vdom_scenes(
         }
         cached_textures.insert(entity, texture);
     }
+    live_images.receive(&live_texture_receiver, &mut vello_renderer);
+
     // Hit-test results reported by workers this frame.
     let mut hit_results: Vec<(Entity, bool)> = Vec::new();
     // Collect painted scenes from all workers and render them.
@@ -432,10 +573,15 @@ fn collect_and_render_vdom_scenes(
                     scene,
                     width,
                     height,
+                    live_images: slots,
                 } => {
+                    let renderable = live_images.record(*entity, slots);
                     let Some(texture) = cached_textures.get(&entity) else {
                         continue;
                     };
+                    if !renderable {
+                        continue;
+                    }
                     if let Err(_err) = vello_renderer.render_to_texture(
                         render_device.wgpu_device(),
                         &render_queue.0,
@@ -456,20 +602,33 @@ fn collect_and_render_vdom_scenes(
                 }
                 VdomResult::HitTestResult { entity: e, caught } => {
                     hit_results.push((e, caught));
+                    hit_tests.write(DioxusUiHitTest { entity: e, caught });
+                }
+                VdomResult::TextInputFocus(rect) => {
+                    worker.focused_text_input = rect;
+                }
+                VdomResult::Rebuilt { panels } => {
+                    debug!("rebuilt document for {}", entity);
+                    // Re-send the current panels on the new channel
+                    if let Ok((mut quad_panels, mut sender)) = panel_channels.get_mut(*entity) {
+                        *sender = DioxusPanelsSender::new(panels);
+                        quad_panels.set_changed();
+                    }
                 }
             }
         }
         span.exit();
     }
+    live_image_requests.0 = live_images.requests(&registry.workers, &mut vello_renderer);
     // Resolve the active pick space once per frame. Window space takes
     // precedence over world space when both caught input.
     if !hit_results.is_empty() {
         let window_caught = hit_results
             .iter()
-            .any(|(e, caught)| *caught && window_uis.contains(*e));
+            .any(|(e, caught)| *caught && ui_spaces.get(*e).is_ok_and(|window| window));
         let world_caught = hit_results
             .iter()
-            .any(|(e, caught)| *caught && world_space_uis.contains(*e));
+            .any(|(e, caught)| *caught && ui_spaces.get(*e).is_ok_and(|window| !window));
         pick_state.active = if window_caught {
             DioxusUiPickFilter::WINDOW_SPACE
         } else if world_caught {
@@ -510,7 +669,10 @@ pub struct DioxusUiResolution(pub u32, pub u32);
 
 /// initialize textures for quads
 fn initialize_textures_for_quads(
-    quads: Query<(Entity, &mut DioxusUiQuad), Without<MeshMaterial3d<StandardMaterial>>>,
+    quads: Query<
+        (Entity, &mut DioxusUiQuad),
+        (Without<MeshMaterial3d<StandardMaterial>>, Without<DioxusUiMirror>),
+    >,
     mut images: ResMut<Assets<Image>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut commands: Commands,
@@ -535,6 +697,78 @@ fn initialize_textures_for_quads(
     }
 }
 
+/// Displays the document of another ui quad instead of owning one.
+///
+/// The mirror shows the source's rendered texture, and input picked on the mirror
+/// is routed to the source's document. The source must not itself be a mirror.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(DioxusUiQuad)]
+pub struct DioxusUiMirror(pub Entity);
+
+/// Points mirror quads at their source's texture and resolution.
+fn sync_mirror_surfaces(
+    mut registry: NonSendMut<VdomThreadRegistry>,
+    mut mirrors: Query<(
+        Entity,
+        &DioxusUiMirror,
+        &mut DioxusUiQuad,
+        Option<&MeshMaterial3d<StandardMaterial>>,
+    )>,
+    sources: Query<&DioxusUiQuad, Without<DioxusUiMirror>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+    // Mirrors already warned about a bad source, so the warning isn't repeated every frame
+    mut invalid: Local<HashSet<Entity>>,
+) {
+    registry.mirrors.clear();
+    invalid.retain(|e| mirrors.contains(*e));
+    for (e, mirror, mut quad, material) in &mut mirrors {
+        registry.mirrors.insert(e, mirror.0);
+
+        let Ok(source) = sources.get(mirror.0) else {
+            if invalid.insert(e) {
+                warn!(
+                    "mirror {} references {}, which is not a source ui quad",
+                    e, mirror.0
+                );
+            }
+            continue;
+        };
+        invalid.remove(&e);
+        if quad.computed_wh != source.computed_wh {
+            quad.computed_wh = source.computed_wh;
+        }
+        let Some(handle) = &source.handle else {
+            continue;
+        };
+        if quad.handle.as_ref() == Some(handle) {
+            continue;
+        }
+        quad.handle = Some(handle.clone());
+
+        match material {
+            Some(material) => {
+                let Some(mut material) = materials.get_mut(&material.0) else {
+                    warn!("material missing for mirror ui quad {}", e);
+                    continue;
+                };
+                material.base_color_texture = Some(handle.clone());
+            }
+            None => {
+                commands
+                    .entity(e)
+                    .insert(MeshMaterial3d(materials.add(StandardMaterial {
+                        base_color_texture: Some(handle.clone()),
+                        unlit: true,
+                        alpha_mode: AlphaMode::Blend,
+                        ..default()
+                    })));
+            }
+        }
+        debug!("mirroring {} onto {}", mirror.0, e);
+    }
+}
+
 /// Set up window surface + camera for window.
 fn setup_window_surface(
     mut commands: Commands,
@@ -0,0 +1,354 @@
+//! Live bevy images, such as camera render targets, drawn inside documents.
+//!
+//! An `img` whose `src` is a [`bevy_image_url`] gets a placeholder image the size of the
+//! texture, so blitz lays it out and paints it like any other image, clipped, scrolled and
+//! stacked with the rest of the document. Vello then copies the GPU texture in place of
+//! the placeholder every time the document's scene is rendered. Until the texture first
+//! arrives, the `img` has no intrinsic size. The texture must have `COPY_SRC` usage and an
+//! `Rgba8Unorm` or `Rgba8UnormSrgb` format.
+
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy_asset::{AssetId, AssetIndex};
+use bevy_derive::Deref;
+use bevy_dioxus_tracing::warn;
+use bevy_ecs::prelude::*;
+use bevy_image::Image;
+use bevy_render::{Extract, render_asset::RenderAssets, texture::GpuImage};
+use blitz_dom::local_name;
+use blitz_dom::node::{ImageData as DomImageData, RasterImageData, SpecialElementData};
+use crossbeam_channel::{Receiver, Sender};
+use dioxus_native::DioxusDocument;
+use vello::Renderer as VelloRenderer;
+use vello::peniko::{Blob, ImageAlphaType, ImageData, ImageFormat};
+
+use crate::worker::{VdomCommand, VdomWorker};
+
+/// Url scheme of live bevy images.
+pub const BEVY_IMAGE_SCHEME: &str = "bevy-image";
+
+/// Url to use as an `img` `src` to display the live texture of `image`.
+pub fn bevy_image_url(image: impl Into<AssetId<Image>>) -> String {
+    match image.into() {
+        AssetId::Index { index, .. } => {
+            format!("{BEVY_IMAGE_SCHEME}://index/{}", index.to_bits())
+        }
+        AssetId::Uuid { uuid } => format!("{BEVY_IMAGE_SCHEME}://uuid/{uuid}"),
+    }
+}
+
+fn parse_bevy_image_url(url: &str) -> Option<AssetId<Image>> {
+    let rest = url.strip_prefix(BEVY_IMAGE_SCHEME)?.strip_prefix("://")?;
+    let (kind, id) = rest.split_once('/')?;
+    match kind {
+        "index" => Some(AssetId::Index {
+            index: AssetIndex::from_bits(id.parse().ok()?),
+            marker: PhantomData,
+        }),
+        "uuid" => Some(AssetId::Uuid {
+            uuid: id.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+/// A live image shown in a painted scene.
+#[derive(Clone, Copy, Debug)]
+pub struct LiveImageSlot {
+    pub image: AssetId<Image>,
+    /// Blob id of the placeholder the `img` was painted with, if it had one.
+    pub placeholder: Option<u64>,
+}
+
+/// Give every live image `img` the placeholder of its image, or none if it has none yet.
+/// Returns whether any changed, so the document needs laying out again.
+pub(crate) fn apply_live_image_placeholders(
+    doc: &DioxusDocument,
+    placeholders: &HashMap<AssetId<Image>, RasterImageData>,
+) -> bool {
+    let mut inner = doc.inner.borrow_mut();
+    let changed: Vec<(usize, Option<RasterImageData>)> = inner
+        .tree()
+        .iter()
+        .filter_map(|(id, node)| {
+            let image = parse_bevy_image_url(node.attr(local_name!("src"))?)?;
+            let current = node.element_data()?.raster_image_data();
+            let placeholder = placeholders.get(&image);
+            let same = match (current, placeholder) {
+                (Some(current), Some(placeholder)) => current.data.id() == placeholder.data.id(),
+                (current, placeholder) => current.is_none() && placeholder.is_none(),
+            };
+            (!same).then(|| (id, placeholder.cloned()))
+        })
+        .collect();
+
+    for (id, placeholder) in &changed {
+        let Some(element) = inner
+            .get_node_mut(*id)
+            .and_then(|node| node.element_data_mut())
+        else {
+            continue;
+        };
+        element.special_data = match placeholder {
+            Some(placeholder) => {
+                SpecialElementData::Image(Box::new(DomImageData::Raster(placeholder.clone())))
+            }
+            None => SpecialElementData::None,
+        };
+        // The image's size is cached up to the root
+        let mut next = Some(*id);
+        while let Some(node) = next.and_then(|id| inner.get_node_mut(id)) {
+            node.cache.clear();
+            next = node.layout_parent.get();
+        }
+    }
+    !changed.is_empty()
+}
+
+/// Find every live image in a document, with the placeholder it is painted with.
+pub(crate) fn collect_live_image_slots(doc: &DioxusDocument) -> Vec<LiveImageSlot> {
+    let inner = doc.inner.borrow();
+    inner
+        .tree()
+        .iter()
+        .filter_map(|(_, node)| {
+            let image = parse_bevy_image_url(node.attr(local_name!("src"))?)?;
+            let placeholder = node
+                .element_data()?
+                .raster_image_data()
+                .map(|raster| raster.data.id());
+            Some(LiveImageSlot { image, placeholder })
+        })
+        .collect()
+}
+
+/// Live images shown by any document, extracted so the render world sends their textures.
+#[derive(Resource, Default, Clone)]
+pub(crate) struct LiveImageRequests(pub HashSet<AssetId<Image>>);
+
+#[derive(Debug)]
+pub(crate) struct LiveTexture {
+    texture: wgpu::Texture,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Resource, Deref, Debug)]
+pub(crate) struct LiveTextureReceiver(pub Receiver<(AssetId<Image>, LiveTexture)>);
+
+#[derive(Resource, Deref, Debug)]
+pub(crate) struct LiveTextureSender(pub Sender<(AssetId<Image>, LiveTexture)>);
+
+pub(crate) fn extract_live_image_requests(
+    mut commands: Commands,
+    requests: Extract<Res<LiveImageRequests>>,
+) {
+    commands.insert_resource(requests.clone());
+}
+
+/// Why vello can't copy from a texture, if it can't.
+fn unsupported_texture(format: wgpu::TextureFormat, usage: wgpu::TextureUsages) -> Option<String> {
+    if !matches!(
+        format,
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb
+    ) {
+        return Some(format!(
+            "{format:?} format, expected Rgba8Unorm or Rgba8UnormSrgb"
+        ));
+    }
+    if !usage.contains(wgpu::TextureUsages::COPY_SRC) {
+        return Some("no COPY_SRC usage".to_string());
+    }
+    None
+}
+
+pub(crate) fn send_live_textures(
+    requests: Res<LiveImageRequests>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    sender: Res<LiveTextureSender>,
+    mut unsupported: Local<HashSet<AssetId<Image>>>,
+) {
+    unsupported.retain(|id| requests.0.contains(id));
+    for id in &requests.0 {
+        let Some(gpu_image) = gpu_images.get(*id) else {
+            continue;
+        };
+        let descriptor = &gpu_image.texture_descriptor;
+        if let Some(reason) = unsupported_texture(descriptor.format, descriptor.usage) {
+            if unsupported.insert(*id) {
+                warn!("can't show live image {:?}: {}", id, reason);
+            }
+            continue;
+        }
+        let _ = sender.send((
+            *id,
+            LiveTexture {
+                texture: (*gpu_image.texture).clone(),
+                width: gpu_image.texture_descriptor.size.width,
+                height: gpu_image.texture_descriptor.size.height,
+            },
+        ));
+    }
+}
+
+/// A live texture, and the placeholder image vello substitutes it for.
+struct LiveImage {
+    texture: wgpu::Texture,
+    placeholder: RasterImageData,
+}
+
+/// A placeholder the size of a texture. It has no pixels, since vello copies the texture
+/// instead of uploading it, so it must never be painted without an override.
+fn empty_placeholder(width: u32, height: u32) -> RasterImageData {
+    RasterImageData {
+        width,
+        height,
+        data: Blob::new(Arc::new(Vec::<u8>::new())),
+    }
+}
+
+/// The image vello sees when a document paints `placeholder`.
+fn vello_image(placeholder: &RasterImageData) -> ImageData {
+    ImageData {
+        data: placeholder.data.clone(),
+        format: ImageFormat::Rgba8,
+        alpha_type: ImageAlphaType::Alpha,
+        width: placeholder.width,
+        height: placeholder.height,
+    }
+}
+
+/// Main world cache of live textures, their placeholders, and the live images each
+/// document showed last.
+#[derive(Default)]
+pub(crate) struct LiveImages {
+    images: HashMap<AssetId<Image>, LiveImage>,
+    /// Placeholders of resized textures, kept until no painted scene shows them.
+    retired: Vec<RasterImageData>,
+    slots: HashMap<Entity, Vec<LiveImageSlot>>,
+    /// Placeholder blob ids sent to each document's worker, by image.
+    sent: HashMap<Entity, HashMap<AssetId<Image>, u64>>,
+}
+
+impl LiveImages {
+    /// Take in textures sent by the render world, pointing vello at any that changed.
+    pub(crate) fn receive(
+        &mut self,
+        receiver: &LiveTextureReceiver,
+        vello_renderer: &mut VelloRenderer,
+    ) {
+        while let Ok((id, live)) = receiver.try_recv() {
+            let placeholder = match self.images.remove(&id) {
+                Some(existing) if existing.texture == live.texture => {
+                    self.images.insert(id, existing);
+                    continue;
+                }
+                Some(existing)
+                    if existing.placeholder.width == live.width
+                        && existing.placeholder.height == live.height =>
+                {
+                    existing.placeholder
+                }
+                existing => {
+                    // Scenes painted before the resize still show the old placeholder
+                    self.retired
+                        .extend(existing.map(|existing| existing.placeholder));
+                    empty_placeholder(live.width, live.height)
+                }
+            };
+            vello_renderer.override_image(
+                &vello_image(&placeholder),
+                Some(wgpu::TexelCopyTextureInfoBase {
+                    texture: live.texture.clone(),
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                }),
+            );
+            self.images.insert(
+                id,
+                LiveImage {
+                    texture: live.texture,
+                    placeholder,
+                },
+            );
+        }
+    }
+
+    /// Record the live images of a freshly painted scene. Returns whether vello can render
+    /// it, which it can't if the scene shows a placeholder that was since dropped. The
+    /// document repaints without it once its worker hears it was dropped.
+    pub(crate) fn record(&mut self, entity: Entity, slots: Vec<LiveImageSlot>) -> bool {
+        let renderable = slots.iter().all(|slot| {
+            slot.placeholder.is_none_or(|placeholder| {
+                self.images
+                    .values()
+                    .map(|live| &live.placeholder)
+                    .chain(&self.retired)
+                    .any(|known| known.data.id() == placeholder)
+            })
+        });
+        self.slots.insert(entity, slots);
+        renderable
+    }
+
+    /// Forget documents that no longer exist and images no document shows, send workers
+    /// the placeholders that changed, then list every image still shown.
+    pub(crate) fn requests(
+        &mut self,
+        workers: &HashMap<Entity, VdomWorker>,
+        vello_renderer: &mut VelloRenderer,
+    ) -> HashSet<AssetId<Image>> {
+        self.slots.retain(|entity, _| workers.contains_key(entity));
+        self.sent.retain(|entity, _| workers.contains_key(entity));
+        let requested: HashSet<AssetId<Image>> = self
+            .slots
+            .values()
+            .flatten()
+            .map(|slot| slot.image)
+            .collect();
+        self.images.retain(|id, live| {
+            let keep = requested.contains(id);
+            if !keep {
+                vello_renderer.override_image(&vello_image(&live.placeholder), None);
+            }
+            keep
+        });
+        let shown: HashSet<u64> = self
+            .slots
+            .values()
+            .flatten()
+            .filter_map(|slot| slot.placeholder)
+            .collect();
+        self.retired.retain(|placeholder| {
+            let keep = shown.contains(&placeholder.data.id());
+            if !keep {
+                vello_renderer.override_image(&vello_image(placeholder), None);
+            }
+            keep
+        });
+
+        for (entity, worker) in workers {
+            let sent = self.sent.entry(*entity).or_default();
+            sent.retain(|id, _| {
+                let keep = self.images.contains_key(id);
+                if !keep {
+                    let _ = worker.cmd_tx.try_send(VdomCommand::LiveImage(*id, None));
+                }
+                keep
+            });
+            for (id, live) in &self.images {
+                let blob = live.placeholder.data.id();
+                if sent.get(id) != Some(&blob) {
+                    sent.insert(*id, blob);
+                    let _ = worker
+                        .cmd_tx
+                        .try_send(VdomCommand::LiveImage(*id, Some(live.placeholder.clone())));
+                }
+            }
+        }
+        requested
+    }
+}
@@ -1,73 +1,805 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
 
+use bevy_asset::{
+    AssetLoadFailedEvent, AssetLoader, AssetPath, LoadContext, io::Reader, prelude::*,
+};
 use bevy_dioxus_interop::HeadElement;
-use bevy_dioxus_tracing::warn;
-use blitz_traits::net::{NetHandler, NetProvider};
+use bevy_dioxus_tracing::{debug, warn};
+use bevy_ecs::prelude::*;
+use bevy_reflect::TypePath;
+use bevy_tasks::IoTaskPool;
+use blitz_traits::net::{NetHandler, NetProvider, Url};
 use bytes::Bytes;
 use crossbeam_channel::Sender;
 use data_url::DataUrl;
-use dioxus_document::{LinkProps, MetaProps, NoOpDocument, ScriptProps, StyleProps};
+use dioxus_document::{LinkProps, MetaProps, ScriptProps, StyleProps};
 
-use crate::DioxusMessage;
+use crate::cache::DioxusFetchCache;
+use crate::eval::{DioxusEval, bevy_eval};
+use crate::head::HeadUpdate;
+use crate::http::HttpQueue;
+use crate::schemes::DioxusSchemes;
+use crate::worker::{VdomCommand, VdomThreadRegistry};
+use crate::{Channel, DioxusMessage, DioxusUiQuad};
 
-pub struct BevyNetProvider;
+/// Raw bytes of a file requested through a `bevy://` url.
+#[derive(Asset, TypePath, Debug)]
+pub(crate) struct BevyNetAsset {
+    bytes: Bytes,
+}
+
+/// Loads any file as raw bytes. Picked by asset type, so it has no extensions.
+#[derive(Default, TypePath)]
+pub(crate) struct BevyNetAssetLoader;
+
+impl AssetLoader for BevyNetAssetLoader {
+    type Asset = BevyNetAsset;
+    type Settings = ();
+    type Error = std::io::Error;
 
-impl BevyNetProvider {
-    pub(crate) fn shared() -> Arc<dyn NetProvider> {
-        Arc::new(Self) as _
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<BevyNetAsset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(BevyNetAsset {
+            bytes: Bytes::from(bytes),
+        })
     }
 }
 
-impl NetProvider for BevyNetProvider {
-    fn fetch(
+#[cfg(feature = "file_watcher")]
+/// A file a document loaded without the asset server, loaded only so bevy watches it.
+#[derive(Asset, TypePath, Debug)]
+pub(crate) struct BevyWatchedFile;
+
+#[cfg(feature = "file_watcher")]
+/// Loads nothing, so watching a file doesn't keep a second copy of it in memory.
+#[derive(Default, TypePath)]
+pub(crate) struct BevyWatchedFileLoader;
+
+#[cfg(feature = "file_watcher")]
+impl AssetLoader for BevyWatchedFileLoader {
+    type Asset = BevyWatchedFile;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
         &self,
-        _doc_id: usize,
-        request: blitz_traits::net::Request,
+        _reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<BevyWatchedFile, Self::Error> {
+        Ok(BevyWatchedFile)
+    }
+}
+
+/// A resource a document failed to load.
+#[derive(Message, Clone, Debug)]
+pub struct DioxusLoadFailed {
+    /// Ui quad of the document that requested the resource.
+    pub entity: Entity,
+    pub url: String,
+    pub reason: String,
+}
+
+pub(crate) type LoadFailureChannel = Channel<DioxusLoadFailed>;
+
+/// Reports the failed loads of one document.
+#[derive(Clone)]
+pub(crate) struct LoadFailures {
+    entity: Entity,
+    sender: Sender<DioxusLoadFailed>,
+}
+
+impl LoadFailures {
+    pub(crate) fn new(entity: Entity, sender: Sender<DioxusLoadFailed>) -> Self {
+        Self { entity, sender }
+    }
+
+    /// Blitz's [`NetHandler`] has no error callback, so a failed load completes with an empty
+    /// body. Blitz then treats it as an empty stylesheet or an undecodable image, rather than
+    /// leaving it pending forever.
+    pub(crate) fn fail(&self, handler: Box<dyn NetHandler>, url: String, reason: impl Display) {
+        let reason = reason.to_string();
+        warn!("failed to load {} in {}: {}", url, self.entity, reason);
+        handler.bytes(url.clone(), Bytes::new());
+        let _ = self.sender.send(DioxusLoadFailed {
+            entity: self.entity,
+            url,
+            reason,
+        });
+    }
+}
+
+enum FetchTarget {
+    Handler {
+        handler: Box<dyn NetHandler>,
+        failures: LoadFailures,
+        /// Dropped with the document that made the request.
+        document: Weak<()>,
+    },
+    /// A shared load, completing every request for the url waiting on the cache.
+    Cache(DioxusFetchCache),
+}
+
+/// Completes one fetch, with the loaded bytes or a failure.
+pub(crate) struct FetchResponder {
+    url: String,
+    target: Option<FetchTarget>,
+}
+
+impl FetchResponder {
+    pub(crate) fn new(
+        url: String,
         handler: Box<dyn NetHandler>,
+        failures: LoadFailures,
+        document: Weak<()>,
+    ) -> Self {
+        Self {
+            url,
+            target: Some(FetchTarget::Handler {
+                handler,
+                failures,
+                document,
+            }),
+        }
+    }
+
+    pub(crate) fn cached(url: String, cache: DioxusFetchCache) -> Self {
+        Self {
+            url,
+            target: Some(FetchTarget::Cache(cache)),
+        }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Whether the document, or any document waiting on a shared load, still exists.
+    pub(crate) fn is_wanted(&self) -> bool {
+        match &self.target {
+            Some(FetchTarget::Handler { document, .. }) => document.strong_count() > 0,
+            Some(FetchTarget::Cache(cache)) => cache.is_wanted(&self.url),
+            None => false,
+        }
+    }
+
+    pub(crate) fn respond(mut self, bytes: Bytes) {
+        match self.target.take() {
+            Some(FetchTarget::Handler { handler, .. }) => handler.bytes(self.url.clone(), bytes),
+            Some(FetchTarget::Cache(cache)) => cache.complete(&self.url, Ok(bytes)),
+            None => {}
+        }
+    }
+
+    pub(crate) fn fail(mut self, reason: impl Display) {
+        match self.target.take() {
+            Some(FetchTarget::Handler {
+                handler, failures, ..
+            }) => failures.fail(handler, self.url.clone(), reason),
+            Some(FetchTarget::Cache(cache)) => cache.complete(&self.url, Err(reason.to_string())),
+            None => {}
+        }
+    }
+}
+
+impl Drop for FetchResponder {
+    fn drop(&mut self) {
+        if let Some(FetchTarget::Cache(cache)) = self.target.take() {
+            cache.abandon(&self.url);
+        }
+    }
+}
+
+/// Writes failures reported from worker threads as [`DioxusLoadFailed`] messages.
+pub(crate) fn forward_load_failures(
+    channel: Res<LoadFailureChannel>,
+    mut failures: MessageWriter<DioxusLoadFailed>,
+) {
+    failures.write_batch(channel.receiver.try_iter());
+}
+
+/// A request sent from a worker to be served by the main world.
+pub(crate) enum BevyFetch {
+    /// Load a `bevy://` url through the asset server.
+    Load {
+        entity: Entity,
+        url: Url,
+        responder: FetchResponder,
+    },
+    /// Watch a file the document loaded some other way, reloading it in the document when
+    /// bevy's asset watcher reports it changed.
+    #[cfg(feature = "file_watcher")]
+    Watch { watcher: FileWatcher, path: PathBuf },
+}
+
+pub(crate) type BevyFetchChannel = Channel<BevyFetch>;
+
+/// A document that references a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FileWatcher {
+    pub entity: Entity,
+    /// Href the document is told to reload. Matched against `href` and `src` attributes as
+    /// written, or resolved against the document's base url.
+    pub href: String,
+    /// Resolved url, which the fetch cache keys the file by.
+    pub url: String,
+}
+
+impl FileWatcher {
+    fn add_to(self, watchers: &mut Vec<FileWatcher>) {
+        if !watchers.contains(&self) {
+            watchers.push(self);
+        }
+    }
+
+    /// Drop the cached file and tell the document to fetch it again.
+    fn reload(&self, fetch_cache: &DioxusFetchCache, registry: &VdomThreadRegistry) {
+        fetch_cache.invalidate(&self.url);
+        let Some(worker) = registry.workers.get(&self.entity) else {
+            return;
+        };
+        let _ = worker
+            .cmd_tx
+            .try_send(VdomCommand::ReloadResource(self.href.clone()));
+        debug!("reloading {} in {}", self.href, self.entity);
+    }
+}
+
+struct BevyNetAssetState {
+    /// Keeps the file loaded so it is hot-reloaded when it changes.
+    handle: Handle<BevyNetAsset>,
+    /// Requests made before the file finished loading.
+    pending: Vec<FetchResponder>,
+    /// Documents that referenced the file.
+    watchers: Vec<FileWatcher>,
+}
+
+#[cfg(feature = "file_watcher")]
+struct WatchedFileState {
+    /// Keeps the file loaded so it is hot-reloaded when it changes.
+    _handle: Handle<BevyWatchedFile>,
+    /// Documents that referenced the file.
+    watchers: Vec<FileWatcher>,
+}
+
+/// Files loaded for `bevy://` urls, or watched for documents.
+#[derive(Resource, Default)]
+pub(crate) struct BevyNetAssets {
+    loaded: HashMap<AssetId<BevyNetAsset>, BevyNetAssetState>,
+    #[cfg(feature = "file_watcher")]
+    watched: HashMap<AssetId<BevyWatchedFile>, WatchedFileState>,
+}
+
+/// Map a `bevy://` url to a bevy asset path.
+///
+/// `bevy://textures/icon.png` loads `textures/icon.png` from the default asset source.
+/// A named asset source goes in the user info, so `bevy://embedded@my_crate/theme.css`
+/// loads `embedded://my_crate/theme.css`.
+pub(crate) fn bevy_url_to_asset_path(url: &Url) -> Option<AssetPath<'static>> {
+    let host = url.host_str()?;
+    let path = format!("{host}{}", url.path());
+    let path = match url.username() {
+        "" => path,
+        source => format!("{source}://{path}"),
+    };
+    AssetPath::try_parse(&path).ok().map(|path| path.into_owned())
+}
+
+impl BevyNetAssets {
+    /// Load `path` for a `bevy://` url, recording the document that references it.
+    fn load(
+        &mut self,
+        asset_server: &AssetServer,
+        path: AssetPath<'static>,
+        watcher: FileWatcher,
+    ) -> &mut BevyNetAssetState {
+        let handle = asset_server.load::<BevyNetAsset>(path);
+        let state = self
+            .loaded
+            .entry(handle.id())
+            .or_insert_with(|| BevyNetAssetState {
+                handle: handle.clone(),
+                pending: Vec::new(),
+                watchers: Vec::new(),
+            });
+        watcher.add_to(&mut state.watchers);
+        state
+    }
+
+    /// Watch `path` for changes, recording the document that references it.
+    #[cfg(feature = "file_watcher")]
+    fn watch(
+        &mut self,
+        asset_server: &AssetServer,
+        path: AssetPath<'static>,
+        watcher: FileWatcher,
     ) {
+        let handle = asset_server.load::<BevyWatchedFile>(path);
+        let state = self
+            .watched
+            .entry(handle.id())
+            .or_insert_with(|| WatchedFileState {
+                _handle: handle,
+                watchers: Vec::new(),
+            });
+        watcher.add_to(&mut state.watchers);
+    }
+
+    /// Forget a despawned document, unloading files nothing else references. Files still
+    /// loading for other requests are unloaded once they are served.
+    fn remove_document(&mut self, entity: Entity) {
+        self.loaded.retain(|_, state| {
+            state.watchers.retain(|watcher| watcher.entity != entity);
+            state.pending.retain(FetchResponder::is_wanted);
+            !state.watchers.is_empty() || !state.pending.is_empty()
+        });
+        #[cfg(feature = "file_watcher")]
+        self.watched.retain(|_, state| {
+            state.watchers.retain(|watcher| watcher.entity != entity);
+            !state.watchers.is_empty()
+        });
+    }
+}
+
+/// Directory bevy's default asset source reads from.
+#[cfg(feature = "file_watcher")]
+#[derive(Resource, Clone, Debug)]
+pub(crate) struct BevyAssetRoot(pub PathBuf);
+
+#[cfg(feature = "file_watcher")]
+impl BevyAssetRoot {
+    /// Asset path of a file inside the asset directory. Paths that don't exist as given,
+    /// like the `/assets/theme.css` path of a `dioxus://` url, are looked up relative to the
+    /// asset directory and to its parent.
+    fn asset_path(&self, path: &Path) -> Option<AssetPath<'static>> {
+        let root = self.0.canonicalize().ok()?;
+        let relative = path.strip_prefix("/").unwrap_or(path);
+        let mut candidates = vec![path.to_path_buf(), root.join(relative)];
+        if let Some(parent) = root.parent() {
+            candidates.push(parent.join(relative));
+        }
+        candidates
+            .into_iter()
+            .filter_map(|candidate| candidate.canonicalize().ok())
+            .find_map(|candidate| {
+                let asset_path = candidate.strip_prefix(&root).ok()?;
+                Some(AssetPath::from_path(asset_path).into_owned())
+            })
+    }
+}
+
+/// Loads files requested through `bevy://` urls, serving them once loaded, and watches
+/// files documents loaded from the asset directory through other schemes.
+pub(crate) fn serve_bevy_fetches(
+    channel: Res<BevyFetchChannel>,
+    asset_server: Res<AssetServer>,
+    #[cfg(feature = "file_watcher")] asset_root: Res<BevyAssetRoot>,
+    assets: Res<Assets<BevyNetAsset>>,
+    mut net_assets: ResMut<BevyNetAssets>,
+) {
+    while let Ok(fetch) = channel.receiver.try_recv() {
+        match fetch {
+            BevyFetch::Load {
+                entity,
+                url,
+                responder,
+            } => {
+                let Some(path) = bevy_url_to_asset_path(&url) else {
+                    responder.fail("invalid bevy asset url");
+                    continue;
+                };
+                let mut href = url.clone();
+                href.set_fragment(None);
+                let watcher = FileWatcher {
+                    entity,
+                    href: href.to_string(),
+                    url: href.to_string(),
+                };
+                let state = net_assets.load(&asset_server, path, watcher);
+                match assets.get(&state.handle) {
+                    Some(asset) => responder.respond(asset.bytes.clone()),
+                    None => state.pending.push(responder),
+                }
+            }
+            #[cfg(feature = "file_watcher")]
+            BevyFetch::Watch { watcher, path } => {
+                // Bevy only watches its asset directory
+                if let Some(path) = asset_root.asset_path(&path) {
+                    net_assets.watch(&asset_server, path, watcher);
+                }
+            }
+        }
+    }
+}
+
+/// Hands loaded files to waiting requests, and reloads them in documents when they change.
+///
+/// Bevy only reports changed files with its `file_watcher` feature enabled.
+pub(crate) fn forward_bevy_net_asset_events(
+    mut events: MessageReader<AssetEvent<BevyNetAsset>>,
+    #[cfg(feature = "file_watcher")] mut watched_events: MessageReader<AssetEvent<BevyWatchedFile>>,
+    mut failed: MessageReader<AssetLoadFailedEvent<BevyNetAsset>>,
+    assets: Res<Assets<BevyNetAsset>>,
+    mut net_assets: ResMut<BevyNetAssets>,
+    fetch_cache: Res<DioxusFetchCache>,
+    registry: NonSend<VdomThreadRegistry>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::LoadedWithDependencies { id } => {
+                let (Some(asset), Some(state)) = (assets.get(*id), net_assets.loaded.get_mut(id))
+                else {
+                    continue;
+                };
+                for responder in state.pending.drain(..) {
+                    responder.respond(asset.bytes.clone());
+                }
+                // Every document that requested it despawned while it loaded
+                if state.watchers.is_empty() {
+                    net_assets.loaded.remove(id);
+                }
+            }
+            AssetEvent::Modified { id } => {
+                let Some(state) = net_assets.loaded.get(id) else {
+                    continue;
+                };
+                for watcher in &state.watchers {
+                    watcher.reload(&fetch_cache, &registry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[cfg(feature = "file_watcher")]
+    for event in watched_events.read() {
+        if let AssetEvent::Modified { id } = event
+            && let Some(state) = net_assets.watched.get(id)
+        {
+            for watcher in &state.watchers {
+                watcher.reload(&fetch_cache, &registry);
+            }
+        }
+    }
+
+    for event in failed.read() {
+        // Dropping the handle lets a later request try loading the file again
+        let Some(state) = net_assets.loaded.remove(&event.id) else {
+            continue;
+        };
+        for responder in state.pending {
+            responder.fail(&event.error);
+        }
+    }
+}
+
+/// Stops watching files for despawned documents.
+pub(crate) fn prune_bevy_net_assets(
+    mut removed: RemovedComponents<DioxusUiQuad>,
+    mut net_assets: ResMut<BevyNetAssets>,
+) {
+    for entity in removed.read() {
+        net_assets.remove_document(entity);
+    }
+}
+
+/// Which files documents may load through `file://` urls.
+///
+/// Unless the app inserts its own, documents may only load files in bevy's asset directory.
+/// The [`Default`] denies every file.
+#[derive(Resource, Clone, Debug)]
+pub struct DioxusFileAccess {
+    /// Directories files must be inside of. `None` allows any file.
+    pub allowed_roots: Option<Vec<PathBuf>>,
+}
+
+impl Default for DioxusFileAccess {
+    fn default() -> Self {
+        Self::sandboxed(Vec::<PathBuf>::new())
+    }
+}
+
+impl DioxusFileAccess {
+    pub fn sandboxed(allowed_roots: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        Self {
+            allowed_roots: Some(allowed_roots.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    /// Allow documents to load any file the game can read. Only for trusted documents.
+    pub fn allow_all() -> Self {
+        Self {
+            allowed_roots: None,
+        }
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        let path = path.canonicalize()?;
+        if let Some(roots) = &self.allowed_roots {
+            let allowed = roots
+                .iter()
+                .filter_map(|root| root.canonicalize().ok())
+                .any(|root| path.starts_with(root));
+            if !allowed {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!("{} is outside of the allowed roots", path.display()),
+                ));
+            }
+        }
+        std::fs::read(path)
+    }
+}
+
+/// Base url that relative `href` and `src` values in this ui quad's document resolve against,
+/// e.g. `bevy://ui/` or `file:///path/to/mod/`. Read when the quad's document is created.
+#[derive(Component, Clone, Debug)]
+pub struct DioxusBaseUrl(pub String);
+
+pub struct BevyNetProvider {
+    loader: FetchLoader,
+    failures: LoadFailures,
+    cache: DioxusFetchCache,
+    /// Dropped with the document, so requests it no longer needs are skipped.
+    alive: Arc<()>,
+}
+
+/// Loads requests for one document's provider. Kept apart from the provider so a shared
+/// load can be restarted without it.
+#[derive(Clone)]
+struct FetchLoader {
+    /// Ui quad the document using this provider renders on.
+    entity: Entity,
+    bevy_fetches: Sender<BevyFetch>,
+    file_access: DioxusFileAccess,
+    schemes: DioxusSchemes,
+    /// Shared http request queue, if http is enabled.
+    http: Option<Arc<HttpQueue>>,
+}
+
+/// Everything a ui quad's [`BevyNetProvider`] needs, kept to give every document built for
+/// the quad a provider of its own.
+#[derive(Clone)]
+pub(crate) struct BevyNetProviderConfig {
+    loader: FetchLoader,
+    failures: LoadFailures,
+    cache: DioxusFetchCache,
+}
+
+impl BevyNetProviderConfig {
+    pub(crate) fn new(
+        entity: Entity,
+        bevy_fetches: Sender<BevyFetch>,
+        load_failures: Sender<DioxusLoadFailed>,
+        file_access: DioxusFileAccess,
+        schemes: DioxusSchemes,
+        http: Option<Arc<HttpQueue>>,
+        cache: DioxusFetchCache,
+    ) -> Self {
+        Self {
+            loader: FetchLoader {
+                entity,
+                bevy_fetches,
+                file_access,
+                schemes,
+                http,
+            },
+            failures: LoadFailures::new(entity, load_failures),
+            cache,
+        }
+    }
+
+    /// A provider for a new document. Requests it makes are given up once the document
+    /// drops it, without touching other documents built from the same config.
+    pub(crate) fn provider(&self) -> Arc<dyn NetProvider> {
+        Arc::new(BevyNetProvider {
+            loader: self.loader.clone(),
+            failures: self.failures.clone(),
+            cache: self.cache.clone(),
+            alive: Arc::new(()),
+        }) as _
+    }
+}
+
+impl FetchLoader {
+    /// Load `request`, completing `responder` with the result.
+    fn load(&self, request: blitz_traits::net::Request, responder: FetchResponder) {
+        if let Some(scheme_handler) = self.schemes.get(request.url.scheme()) {
+            let fetch = scheme_handler.fetch(request);
+            IoTaskPool::get()
+                .spawn(async move {
+                    let result = fetch.await;
+                    if !responder.is_wanted() {
+                        return;
+                    }
+                    match result {
+                        Ok(bytes) => responder.respond(bytes),
+                        Err(err) => responder.fail(err),
+                    }
+                })
+                .detach();
+            return;
+        }
+
         match request.url.scheme() {
             // Load Dioxus assets
             "dioxus" => match dioxus_asset_resolver::native::serve_asset(request.url.path()) {
-                Ok(res) => handler.bytes(request.url.to_string(), res.into_body().into()),
-                Err(_err) => {
-                    warn!("{_err}");
-                }
+                Ok(res) => responder.respond(res.into_body().into()),
+                Err(err) => responder.fail(err),
             },
             // Decode data URIs
             "data" => {
-                let Ok(data_url) = DataUrl::process(request.url.as_str()) else {
-                    return;
+                let decoded = DataUrl::process(request.url.as_str())
+                    .map_err(|err| format!("invalid data url: {err:?}"))
+                    .and_then(|data_url| {
+                        data_url
+                            .decode_to_vec()
+                            .map_err(|err| format!("invalid data url body: {err:?}"))
+                    });
+                match decoded {
+                    Ok((bytes, _)) => responder.respond(Bytes::from(bytes)),
+                    Err(reason) => responder.fail(reason),
+                }
+            }
+            // Load files through bevy's asset server
+            "bevy" => {
+                let fetch = BevyFetch::Load {
+                    entity: self.entity,
+                    url: request.url,
+                    responder,
                 };
-                let Ok(decoded) = data_url.decode_to_vec() else {
+                if let Err(err) = self.bevy_fetches.send(fetch)
+                    && let BevyFetch::Load { responder, .. } = err.into_inner()
+                {
+                    responder.fail("main world channel closed");
+                }
+            }
+            // Read local files off the worker thread
+            "file" => {
+                let Ok(path) = request.url.to_file_path() else {
+                    responder.fail("invalid file url");
                     return;
                 };
-                let bytes = Bytes::from(decoded.0);
-                handler.bytes(request.url.to_string(), bytes);
-            }
-            // TODO: support http requests
-            _ => {
-                warn!(
-                    "unsupported scheme detected for {_doc_id} for request {:#?}",
-                    request
-                );
+                let file_access = self.file_access.clone();
+                IoTaskPool::get()
+                    .spawn(async move {
+                        match file_access.read(&path) {
+                            Ok(bytes) => responder.respond(Bytes::from(bytes)),
+                            Err(err) => responder.fail(err),
+                        }
+                    })
+                    .detach();
             }
+            "http" | "https" => match &self.http {
+                Some(http) => http.push(request, responder),
+                None => {
+                    responder.fail("http is disabled, insert a `DioxusHttp` resource to enable it")
+                }
+            },
+            scheme => responder.fail(format!("unsupported scheme `{scheme}`")),
+        }
+    }
+}
+
+impl Drop for BevyNetProvider {
+    fn drop(&mut self) {
+        // Give up this document's requests, then stop the ones no other document wants
+        self.alive = Arc::new(());
+        if let Some(http) = &self.loader.http {
+            http.cancel_unwanted();
+        }
+    }
+}
+
+impl NetProvider for BevyNetProvider {
+    fn fetch(
+        &self,
+        _doc_id: usize,
+        request: blitz_traits::net::Request,
+        handler: Box<dyn NetHandler>,
+    ) {
+        let url = request.url.to_string();
+        let scheme = request.url.scheme();
+        // Sized and painted from the GPU texture through a placeholder, see `live_image`
+        if scheme == crate::live_image::BEVY_IMAGE_SCHEME {
+            return;
+        }
+
+        // Reload files from the asset directory when bevy sees them change
+        #[cfg(feature = "file_watcher")]
+        let watch_path = match scheme {
+            "dioxus" => Some(PathBuf::from(request.url.path())),
+            "file" => request.url.to_file_path().ok(),
+            _ => None,
+        };
+        #[cfg(feature = "file_watcher")]
+        if let Some(path) = watch_path {
+            let mut resolved = request.url.clone();
+            resolved.set_fragment(None);
+            let href = match scheme {
+                // As written by `asset!()`, and sent by devserver hot reloads
+                "dioxus" => request.url.path().to_string(),
+                _ => resolved.to_string(),
+            };
+            let _ = self.loader.bevy_fetches.send(BevyFetch::Watch {
+                watcher: FileWatcher {
+                    entity: self.loader.entity,
+                    href,
+                    url: resolved.to_string(),
+                },
+                path,
+            });
+        }
+
+        let responder = FetchResponder::new(
+            url,
+            handler,
+            self.failures.clone(),
+            Arc::downgrade(&self.alive),
+        );
+        // Data urls are cheap to decode, and the asset server already shares bevy files
+        if matches!(scheme, "data" | "bevy") {
+            self.loader.load(request, responder);
+        } else {
+            let loader = self.loader.clone();
+            self.cache.fetch(
+                responder,
+                Arc::new(move |responder| loader.load(request.clone(), responder)),
+            );
         }
     }
 }
 
 pub struct DioxusDocumentProxy {
     sender: Sender<DioxusMessage>,
+    /// Ui quad the document renders on.
+    entity: Entity,
+    head: Sender<(Entity, HeadUpdate)>,
+    evals: Sender<DioxusEval>,
+    /// Loads head resources the way the document loads its own.
+    #[cfg_attr(not(feature = "window_icon"), allow(dead_code))]
+    net_provider: Arc<dyn NetProvider>,
+    #[cfg_attr(not(feature = "window_icon"), allow(dead_code))]
+    base_url: Option<Url>,
+    /// Whether the document is the window ui quad's, so its icon is fetched for the window.
+    #[cfg_attr(not(feature = "window_icon"), allow(dead_code))]
+    window_ui: bool,
 }
 
 impl DioxusDocumentProxy {
-    pub(crate) fn new(sender: Sender<DioxusMessage>) -> Self {
-        Self { sender }
+    pub(crate) fn new(
+        sender: Sender<DioxusMessage>,
+        entity: Entity,
+        head: Sender<(Entity, HeadUpdate)>,
+        evals: Sender<DioxusEval>,
+        net_provider: Arc<dyn NetProvider>,
+        base_url: Option<Url>,
+        window_ui: bool,
+    ) -> Self {
+        Self {
+            sender,
+            entity,
+            head,
+            evals,
+            net_provider,
+            base_url,
+            window_ui,
+        }
     }
 }
 
 impl dioxus_document::Document for DioxusDocumentProxy {
-    fn eval(&self, _js: String) -> dioxus_document::Eval {
-        NoOpDocument.eval(_js)
+    fn eval(&self, js: String) -> dioxus_document::Eval {
+        bevy_eval(self.entity, js, &self.evals)
     }
 
     fn create_head_element(
@@ -89,6 +821,9 @@ impl dioxus_document::Document for DioxusDocumentProxy {
     }
 
     fn set_title(&self, title: String) {
+        let _ = self
+            .head
+            .send((self.entity, HeadUpdate::Title(title.clone())));
         self.create_head_element("title", &[], Some(title));
     }
 
@@ -108,6 +843,25 @@ impl dioxus_document::Document for DioxusDocumentProxy {
     }
 
     fn create_link(&self, props: LinkProps) {
+        let is_icon = props
+            .rel
+            .as_deref()
+            .is_some_and(|rel| rel.split_whitespace().any(|rel| rel == "icon"));
+        if let (true, Some(href)) = (is_icon, &props.href) {
+            let _ = self
+                .head
+                .send((self.entity, HeadUpdate::Icon(href.clone())));
+            #[cfg(feature = "window_icon")]
+            if self.window_ui {
+                crate::head::fetch_icon(
+                    &self.net_provider,
+                    self.base_url.as_ref(),
+                    self.entity,
+                    href.clone(),
+                    self.head.clone(),
+                );
+            }
+        }
         let attributes = props.attributes();
         self.create_head_element("link", &attributes, None);
     }
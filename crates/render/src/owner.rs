@@ -0,0 +1,57 @@
+//! Access to the bevy entity a dioxus document is rendered on.
+
+use bevy_ecs::prelude::*;
+use dioxus_hooks::use_context;
+
+/// Root context of every document, identifying the ui quad it renders on.
+///
+/// Lets one panel component be reused across many entities by querying
+/// its "own" components.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnerEntity {
+    /// The [`crate::DioxusUiQuad`] entity that owns the document.
+    pub entity: Entity,
+    /// Ancestors of the owner from its direct parent up to the hierarchy root,
+    /// captured when the document was created.
+    ///
+    /// This is a snapshot: it isn't updated when the owner or one of its ancestors is
+    /// reparented afterwards, not even by a devserver rebuild of the document. Query
+    /// [`ChildOf`] through the owner entity for the current hierarchy.
+    pub ancestors: Vec<Entity>,
+}
+
+impl OwnerEntity {
+    pub(crate) fn new(entity: Entity, parents: &Query<&ChildOf>) -> Self {
+        Self {
+            entity,
+            ancestors: parents.iter_ancestors(entity).collect(),
+        }
+    }
+
+    /// Direct parent of the owner, if it has one.
+    pub fn parent(&self) -> Option<Entity> {
+        self.ancestors.first().copied()
+    }
+
+    /// Topmost ancestor of the owner, or the owner itself if it has no parent.
+    pub fn root(&self) -> Entity {
+        self.ancestors.last().copied().unwrap_or(self.entity)
+    }
+}
+
+/// Entity of the ui quad this component is rendered on.
+pub fn use_owner_entity() -> Entity {
+    use_context::<OwnerEntity>().entity
+}
+
+/// Parent of the ui quad this component is rendered on, when its document was created.
+/// See [`OwnerEntity::ancestors`].
+pub fn use_owner_parent() -> Option<Entity> {
+    use_context::<OwnerEntity>().parent()
+}
+
+/// Ancestors of the ui quad this component is rendered on, nearest first, when its
+/// document was created. See [`OwnerEntity::ancestors`].
+pub fn use_owner_ancestors() -> Vec<Entity> {
+    use_context::<OwnerEntity>().ancestors
+}
@@ -6,27 +6,120 @@ use bevy_dioxus_interop::DioxusMessage;
 use bevy_dioxus_tracing::{error, warn};
 use bevy_ecs::prelude::*;
 use bevy_utils::default;
-use blitz_dom::DocumentConfig;
-#[cfg(target_os = "linux")]
-use blitz_dom::FontContext;
+use blitz_dom::{DocumentConfig, FontContext};
+use blitz_traits::net::Url;
+use crossbeam_channel::Sender;
 use dioxus_bevy_signals::CommandQueueSender;
 use dioxus_core::{Element, ScopeId, VirtualDom, provide_context};
 use dioxus_native::DioxusDocument;
-#[cfg(target_os = "linux")]
-use vello::peniko::Blob;
 
-use crate::net_provider::{BevyNetProvider, DioxusDocumentProxy};
+use crate::cache::DioxusFetchCache;
+use crate::eval::{DioxusEval, EvalChannel};
+use crate::fonts::SharedFontContext;
+use crate::head::{DocumentHeadChannel, HeadUpdate};
+use crate::http::{DioxusHttp, HttpQueue};
+use crate::layout::{PanelAnchor, PanelLayout};
+use crate::net_provider::{
+    BevyFetchChannel, BevyNetProviderConfig, DioxusBaseUrl, DioxusDocumentProxy, DioxusFileAccess,
+    LoadFailureChannel,
+};
+use crate::owner::OwnerEntity;
+use crate::schemes::DioxusSchemes;
 use crate::worker::{VdomThreadRegistry, VdomWorker};
-use crate::{DioxusUiQuad, dioxus_ui};
+use crate::{DefaultDioxusRoot, DioxusRoot, DioxusUiMirror, DioxusUiQuad, DioxusWindowUiQuad};
+
+/// Render function of a panel. Runs inside the panel's own component scope, so it
+/// may call hooks and capture props or other state.
+pub type PanelRender = Arc<dyn Fn() -> Element + Send + Sync>;
+
+/// A keyed panel and where it is laid out on its surface.
+#[derive(Clone)]
+pub struct PanelSpec {
+    /// Identifies the panel on its surface. Only one panel per key is allowed.
+    pub key: String,
+    pub render: PanelRender,
+    pub layout: PanelLayout,
+    /// Hidden panels stay mounted, so their state survives being shown again.
+    pub visible: bool,
+}
+
+impl PanelSpec {
+    pub fn new(key: impl Into<String>, render: impl Fn() -> Element + Send + Sync + 'static) -> Self {
+        Self {
+            key: key.into(),
+            render: Arc::new(render),
+            layout: PanelLayout::default(),
+            visible: true,
+        }
+    }
+
+    /// Panel from a plain function, keyed by the function's address.
+    pub fn from_fn(panel: fn() -> Element) -> Self {
+        Self::new(format!("{:p}", panel as *const ()), panel)
+    }
+
+    /// Panel from a component and the props to render it with.
+    pub fn from_component<P>(key: impl Into<String>, component: fn(P) -> Element, props: P) -> Self
+    where
+        P: Clone + Send + Sync + 'static,
+    {
+        Self::new(key, move || component(props.clone()))
+    }
+
+    pub fn with_layout(mut self, layout: PanelLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Key of the element wrapping this panel in [`crate::dioxus_ui`].
+    ///
+    /// Includes the render function's identity, so replacing a panel remounts it
+    /// while reordering or hiding it keeps its component state.
+    pub(crate) fn dom_key(&self) -> String {
+        format!("{}@{:p}", self.key, Arc::as_ptr(&self.render) as *const ())
+    }
+
+    /// Inline style for the element wrapping this panel in [`crate::dioxus_ui`].
+    pub(crate) fn wrapper_style(&self) -> String {
+        let style = self.layout.wrapper_style();
+        if self.visible {
+            style
+        } else {
+            style + " display: none;"
+        }
+    }
+}
+
+impl PartialEq for PanelSpec {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+            && Arc::ptr_eq(&self.render, &other.render)
+            && self.layout == other.layout
+            && self.visible == other.visible
+    }
+}
+
+impl std::fmt::Debug for PanelSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PanelSpec")
+            .field("key", &self.key)
+            .field("layout", &self.layout)
+            .field("visible", &self.visible)
+            .finish_non_exhaustive()
+    }
+}
 
 /// Panels on a dioxus ui quad surface
-///
-/// TODO: support multiple panel orientations (left, right, bottom, top, etc..)
 #[derive(Component, Clone, Default)]
 #[require(DioxusUiQuad)]
 pub struct DioxusPanels {
     /// Panels in insertion order.
-    pub panels: Vec<fn() -> Element>,
+    pub panels: Vec<PanelSpec>,
 }
 
 impl DioxusPanels {
@@ -38,16 +131,102 @@ impl DioxusPanels {
         set
     }
 
+    /// Insert a panel that fills the center of the surface.
     pub fn insert(&mut self, panel: fn() -> Element) {
-        if !self.panels.contains(&panel) {
+        self.insert_spec(PanelSpec::from_fn(panel));
+    }
+
+    pub fn insert_with_layout(&mut self, panel: fn() -> Element, layout: PanelLayout) {
+        self.insert_spec(PanelSpec::from_fn(panel).with_layout(layout));
+    }
+
+    pub fn insert_spec(&mut self, panel: PanelSpec) {
+        if !self.panels.iter().any(|existing| existing.key == panel.key) {
             self.panels.push(panel);
         } else {
             warn!(
-                "attempted to push panel when one of the same kind already existed for: {:#?}",
-                panel
+                "attempted to push panel when one with the same key already existed for: {}",
+                panel.key
             )
         }
     }
+
+    /// Replace the panel with the same key as `panel`, or insert it if there is none.
+    /// The replaced panel is remounted, dropping its component state.
+    pub fn replace(&mut self, panel: PanelSpec) -> Option<PanelSpec> {
+        match self.panels.iter_mut().find(|existing| existing.key == panel.key) {
+            Some(existing) => Some(std::mem::replace(existing, panel)),
+            None => {
+                self.panels.push(panel);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<PanelSpec> {
+        let index = self.panels.iter().position(|panel| panel.key == key)?;
+        Some(self.panels.remove(index))
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.panels.iter().any(|panel| panel.key == key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&PanelSpec> {
+        self.panels.iter().find(|panel| panel.key == key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut PanelSpec> {
+        self.panels.iter_mut().find(|panel| panel.key == key)
+    }
+
+    /// Show or hide a panel. Returns false if no panel has `key`.
+    pub fn set_visible(&mut self, key: &str, visible: bool) -> bool {
+        let Some(panel) = self.get_mut(key) else {
+            return false;
+        };
+        panel.visible = visible;
+        true
+    }
+
+    /// Flip a panel's visibility, returning the new visibility.
+    pub fn toggle_visible(&mut self, key: &str) -> Option<bool> {
+        let panel = self.get_mut(key)?;
+        panel.visible = !panel.visible;
+        Some(panel.visible)
+    }
+
+    /// Draw a panel above every other panel sharing its anchor.
+    /// Returns false if no panel has `key`.
+    pub fn move_to_front(&mut self, key: &str) -> bool {
+        let Some(index) = self.panels.iter().position(|panel| panel.key == key) else {
+            return false;
+        };
+        let mut panel = self.panels.remove(index);
+        let top = self
+            .panels
+            .iter()
+            .filter(|other| other.layout.anchor == panel.layout.anchor)
+            .map(|other| other.layout.z_index)
+            .max();
+        if let Some(top) = top {
+            panel.layout.z_index = panel.layout.z_index.max(top.saturating_add(1));
+        }
+        self.panels.push(panel);
+        true
+    }
+
+    /// Panels docked to `anchor`, ordered from lowest to highest z-order.
+    pub fn anchored(&self, anchor: PanelAnchor) -> Vec<PanelSpec> {
+        let mut anchored: Vec<PanelSpec> = self
+            .panels
+            .iter()
+            .filter(|panel| panel.layout.anchor == anchor)
+            .cloned()
+            .collect();
+        anchored.sort_by_key(|panel| panel.layout.z_index);
+        anchored
+    }
 }
 
 #[derive(Component)]
@@ -55,88 +234,156 @@ pub struct DioxusPanelsSender {
     sender: crossbeam_channel::Sender<DioxusPanels>,
 }
 
+impl DioxusPanelsSender {
+    pub(crate) fn new(sender: crossbeam_channel::Sender<DioxusPanels>) -> Self {
+        Self { sender }
+    }
+}
+
 #[derive(Clone)]
 pub struct DioxusPanelsReceiver(pub crossbeam_channel::Receiver<DioxusPanels>);
 
 #[derive(Component)]
 pub struct InitializedVdom;
 
-/// On Linux, when blitz can't find a font, it will silently fail to render text.
-/// This work around forcibly includes the below workaround font.
-///
-/// This work around also points blitz's default stylesheet fonts to this font.
-#[cfg(target_os = "linux")]
-fn setup_fallback_font() -> FontContext {
-    let font_data: &'static [u8] = include_bytes!("../../../assets/JetBrainsMono-Medium.ttf");
-    let mut font_ctx = FontContext::default();
-    let families = font_ctx
-        .collection
-        .register_fonts(Blob::from(font_data.to_vec()), None);
-    if let Some((family_id, _)) = families.first() {
-        use parley::fontique::GenericFamily::*;
-        for generic in [Serif, SansSerif, Monospace, Cursive, Fantasy, SystemUi] {
-            font_ctx
-                .collection
-                .set_generic_families(generic, std::iter::once(*family_id));
-        }
-    }
-    font_ctx
-}
-
-#[cfg(not(target_os = "linux"))]
-fn setup_fallback_font() -> FontContext {
-    FontContext::default()
+/// Everything needed to build a ui quad's document. Kept by its worker to rebuild the
+/// document when the devserver does a full reload.
+pub struct DocumentBuilder {
+    entity: Entity,
+    root: fn() -> Element,
+    owner: OwnerEntity,
+    command_queue_sender: CommandQueueSender,
+    font_ctx: FontContext,
+    base_url: Option<String>,
+    /// Whether this is the window ui quad's document, whose icon becomes the window icon.
+    window_ui: bool,
+    net_provider: BevyNetProviderConfig,
+    proxy_sender: Sender<DioxusMessage>,
+    document_heads: Sender<(Entity, HeadUpdate)>,
+    evals: Sender<DioxusEval>,
 }
 
-/// Spawns a worker thread for each ui quad that has no VDOM yet.
-pub(crate) fn initialize_vdoms(
-    quads: Query<(Entity, &DioxusUiQuad), Without<InitializedVdom>>,
-    mut registry: NonSendMut<VdomThreadRegistry>,
-    command_queue_sender: Res<CommandQueueSender>,
-    mut commands: Commands,
-) {
-    for (e, _quad) in quads {
-        if registry.workers.contains_key(&e) {
-            warn!(
-                "document initialization requested for {} but worker already exists",
-                e
-            );
-            continue;
-        }
+impl DocumentBuilder {
+    /// Url relative `href` and `src` values in the document resolve against.
+    pub(crate) fn base_url(&self) -> Option<Url> {
+        Url::parse(self.base_url.as_deref()?).ok()
+    }
 
+    /// Build the document and run its first render. Panels for it are sent on the
+    /// returned sender.
+    pub(crate) fn build(&self) -> (DioxusDocument, Sender<DioxusPanels>) {
         let (panel_sender, panel_receiver) = crossbeam_channel::unbounded::<DioxusPanels>();
-        let (proxy_sender, proxy_receiver) = crossbeam_channel::unbounded::<DioxusMessage>();
 
-        let vdom = VirtualDom::new_with_props(dioxus_ui, ())
+        let vdom = VirtualDom::new_with_props(self.root, ())
             .with_root_context(DioxusPanelsReceiver(panel_receiver))
-            .with_root_context(command_queue_sender.clone());
-
-        let font_ctx = setup_fallback_font();
+            .with_root_context(self.owner.clone())
+            .with_root_context(self.command_queue_sender.clone());
 
         let mut dioxus_doc = DioxusDocument::new(
             vdom,
             DocumentConfig {
-                font_ctx: Some(font_ctx),
+                font_ctx: Some(self.font_ctx.clone()),
                 ua_stylesheets: Some(vec![blitz_dom::DEFAULT_CSS.to_string()]),
+                base_url: self.base_url.clone(),
                 ..default()
             },
         );
+        // A fresh provider, so rebuilding gives up the requests of the document it replaces
+        let net_provider = self.net_provider.provider();
+        dioxus_doc
+            .inner
+            .borrow_mut()
+            .set_net_provider(net_provider.clone());
 
-        let net_provider = BevyNetProvider::shared();
-        dioxus_doc.inner.borrow_mut().set_net_provider(net_provider);
-
-        let proxy = Rc::new(DioxusDocumentProxy::new(proxy_sender.clone()));
+        let proxy = Rc::new(DioxusDocumentProxy::new(
+            self.proxy_sender.clone(),
+            self.entity,
+            self.document_heads.clone(),
+            self.evals.clone(),
+            net_provider,
+            self.base_url(),
+            self.window_ui,
+        ));
         dioxus_doc.vdom.in_scope(ScopeId::ROOT, move || {
             provide_context(proxy as Rc<dyn dioxus_document::Document>);
         });
 
-        dioxus_devtools::connect(move |msg| {
-            if proxy_sender.send(DioxusMessage::Devserver(msg)).is_err() {
-                warn!("devtools message dropped, vdom worker channel closed");
-            }
-        });
-
         dioxus_doc.initial_build();
+        (dioxus_doc, panel_sender)
+    }
+}
+
+/// Spawns a worker thread for each ui quad that has no VDOM yet. Mirrors share their
+/// source's worker instead.
+pub(crate) fn initialize_vdoms(
+    quads: Query<
+        (
+            Entity,
+            &DioxusUiQuad,
+            Option<&DioxusRoot>,
+            Option<&DioxusBaseUrl>,
+            Has<DioxusWindowUiQuad>,
+        ),
+        (Without<InitializedVdom>, Without<DioxusUiMirror>),
+    >,
+    parents: Query<&ChildOf>,
+    default_root: Res<DefaultDioxusRoot>,
+    shared_fonts: Res<SharedFontContext>,
+    bevy_fetches: Res<BevyFetchChannel>,
+    load_failures: Res<LoadFailureChannel>,
+    file_access: Res<DioxusFileAccess>,
+    schemes: Res<DioxusSchemes>,
+    fetch_cache: Res<DioxusFetchCache>,
+    document_heads: Res<DocumentHeadChannel>,
+    evals: Res<EvalChannel>,
+    http: Option<Res<DioxusHttp>>,
+    mut http_queue: Local<Option<Arc<HttpQueue>>>,
+    mut registry: NonSendMut<VdomThreadRegistry>,
+    command_queue_sender: Res<CommandQueueSender>,
+    mut commands: Commands,
+) {
+    match http {
+        Some(http) if http.is_changed() || http_queue.is_none() => {
+            *http_queue = Some(HttpQueue::new(http.clone()));
+        }
+        Some(_) => {}
+        None => *http_queue = None,
+    }
+
+    for (e, _quad, root, base_url, window_ui) in quads {
+        if registry.workers.contains_key(&e) {
+            warn!(
+                "document initialization requested for {} but worker already exists",
+                e
+            );
+            continue;
+        }
+
+        let (proxy_sender, proxy_receiver) = crossbeam_channel::unbounded::<DioxusMessage>();
+
+        let net_provider = BevyNetProviderConfig::new(
+            e,
+            bevy_fetches.sender.clone(),
+            load_failures.sender.clone(),
+            file_access.clone(),
+            schemes.clone(),
+            http_queue.clone(),
+            fetch_cache.clone(),
+        );
+        let builder = DocumentBuilder {
+            entity: e,
+            root: root.map(|root| root.0).unwrap_or(default_root.0),
+            owner: OwnerEntity::new(e, &parents),
+            command_queue_sender: command_queue_sender.clone(),
+            font_ctx: shared_fonts.document_font_ctx(),
+            base_url: base_url.map(|base_url| base_url.0.clone()),
+            window_ui,
+            net_provider,
+            proxy_sender,
+            document_heads: document_heads.sender.clone(),
+            evals: evals.sender.clone(),
+        };
+        let (dioxus_doc, panel_sender) = builder.build();
 
         // Set up channels for worker communication.
         let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
@@ -147,6 +394,7 @@ pub(crate) fn initialize_vdoms(
         let handle = VdomWorker::spawn(
             e,
             dioxus_doc,
+            builder,
             proxy_receiver,
             cmd_rx,
             result_tx,
@@ -160,12 +408,13 @@ pub(crate) fn initialize_vdoms(
             input_tx,
             waker_flag,
             thread: Some(handle),
+            focused_text_input: None,
         };
         registry.workers.insert(e, worker);
 
-        commands.entity(e).insert(DioxusPanelsSender {
-            sender: panel_sender,
-        });
+        commands
+            .entity(e)
+            .insert(DioxusPanelsSender::new(panel_sender));
         commands.entity(e).insert(InitializedVdom);
     }
 }
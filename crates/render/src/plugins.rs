@@ -1,19 +1,42 @@
 use std::time::Instant;
 
 use bevy_app::prelude::*;
+use bevy_asset::io::file::FileAssetReader;
+use bevy_asset::{AssetApp, AssetPlugin};
 use bevy_render::{Render, RenderApp, RenderSystems, renderer::RenderDevice};
 use vello::RendererOptions;
 
+use crate::cache::DioxusFetchCache;
+use crate::devtools::{DevserverMessages, forward_devserver_messages};
+use crate::eval::{DioxusEvalHandlers, EvalChannel, dispatch_evals};
+use crate::fonts::{
+    DioxusFont, DioxusFontLoader, DioxusFonts, SharedFontContext, sync_dioxus_fonts,
+};
+use crate::head::{DocumentHeadChannel, sync_document_heads};
+use crate::live_image::{
+    LiveImageRequests, LiveTextureReceiver, LiveTextureSender, extract_live_image_requests,
+    send_live_textures,
+};
+use crate::net_provider::{
+    BevyFetchChannel, BevyNetAsset, BevyNetAssetLoader, BevyNetAssets, DioxusFileAccess,
+    DioxusLoadFailed, LoadFailureChannel, forward_bevy_net_asset_events, forward_load_failures,
+    prune_bevy_net_assets, serve_bevy_fetches,
+};
 use crate::panels::{initialize_vdoms, sync_dioxus_ui_with_panels};
 use crate::schedule::{
     DioxusRenderMain, DioxusRenderSchedule, DioxusRenderScheduleAccumulator,
     DioxusRenderScheduleTimestep,
 };
+use crate::schemes::DioxusSchemes;
 use crate::worker::VdomThreadRegistry;
 use crate::*;
 
 pub struct DioxusRenderPlugin {
     pub fps_cap: u32,
+    /// Root component for ui quads without a [`DioxusRoot`]. Defaults to [`dioxus_ui`].
+    pub default_root: Option<fn() -> Element>,
+    /// Connect to the dioxus devserver for hot reloading. Turn off for release builds.
+    pub devtools: bool,
 }
 
 impl Plugin for DioxusRenderPlugin {
@@ -21,9 +44,60 @@ impl Plugin for DioxusRenderPlugin {
         let epoch = AnimationTime(Instant::now());
 
         app.add_schedule(Schedule::new(DioxusRenderSchedule));
+        app.add_message::<DioxusUiHitTest>();
+
+        app.init_asset::<DioxusFont>()
+            .init_asset_loader::<DioxusFontLoader>()
+            .init_resource::<DioxusFonts>()
+            .init_resource::<SharedFontContext>();
+
+        app.init_asset::<BevyNetAsset>()
+            .init_asset_loader::<BevyNetAssetLoader>()
+            .init_resource::<BevyFetchChannel>()
+            .init_resource::<BevyNetAssets>()
+            .init_resource::<DioxusSchemes>()
+            .init_resource::<DioxusFetchCache>()
+            .init_resource::<LoadFailureChannel>()
+            .add_message::<DioxusLoadFailed>();
+        #[cfg(feature = "file_watcher")]
+        app.init_asset::<crate::net_provider::BevyWatchedFile>()
+            .init_asset_loader::<crate::net_provider::BevyWatchedFileLoader>();
+
+        app.init_resource::<DocumentHeadChannel>()
+            .add_systems(Update, sync_document_heads);
+        app.init_resource::<EvalChannel>()
+            .init_resource::<DioxusEvalHandlers>()
+            .add_systems(Update, dispatch_evals);
+        #[cfg(feature = "window_icon")]
+        app.init_resource::<crate::head::WindowIconState>()
+            .add_systems(
+                Update,
+                crate::head::sync_window_icon.after(sync_document_heads),
+            );
+
+        if self.devtools {
+            app.insert_resource(DevserverMessages::connect())
+                .add_systems(Update, forward_devserver_messages);
+        }
 
         app.insert_non_send(VdomThreadRegistry::default());
         app.insert_resource(epoch);
+        app.insert_resource(self.default_root.map(DefaultDioxusRoot).unwrap_or_default());
+
+        // These read asset events, so they run every frame rather than in the fps capped schedule.
+        app.add_systems(
+            Update,
+            (
+                sync_dioxus_fonts,
+                (
+                    serve_bevy_fetches,
+                    forward_bevy_net_asset_events,
+                    forward_load_failures,
+                    prune_bevy_net_assets,
+                )
+                    .chain(),
+            ),
+        );
 
         app.add_systems(Startup, setup_window_surface)
             .add_systems(PreUpdate, initialize_vdoms)
@@ -36,6 +110,7 @@ impl Plugin for DioxusRenderPlugin {
                     recompute_dioxus_ui_quad_surface,
                     recompute_blitz_render_surfaces,
                     initialize_textures_for_quads,
+                    sync_mirror_surfaces,
                     dispatch_vdom_polls,
                     collect_and_render_vdom_scenes,
                 )
@@ -46,6 +121,19 @@ impl Plugin for DioxusRenderPlugin {
         app.add_systems(Update, DioxusRenderMain::run_dioxus_render_main);
     }
     fn finish(&self, app: &mut App) {
+        // Find the asset directory, to sandbox file urls to it and watch files loaded from it
+        let asset_file_path = app
+            .get_added_plugins::<AssetPlugin>()
+            .first()
+            .map(|plugin| plugin.file_path.clone())
+            .unwrap_or_else(|| "assets".to_string());
+        let asset_root = FileAssetReader::get_base_path().join(asset_file_path);
+        if !app.world().contains_resource::<DioxusFileAccess>() {
+            app.insert_resource(DioxusFileAccess::sandboxed([asset_root.clone()]));
+        }
+        #[cfg(feature = "file_watcher")]
+        app.insert_resource(crate::net_provider::BevyAssetRoot(asset_root));
+
         // Add the UI rendrer
         let render_app = app.sub_app(RenderApp);
         let render_device = render_app.world().resource::<RenderDevice>();
@@ -62,10 +150,19 @@ impl Plugin for DioxusRenderPlugin {
         render_app.insert_resource(RenderWorldSender(s));
         render_app.insert_resource(ExtractedTextureImages::default());
 
+        // Same for live images shown inside documents
+        let (s, r) = crossbeam_channel::unbounded();
+        app.insert_resource(LiveTextureReceiver(r));
+        app.init_resource::<LiveImageRequests>();
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_systems(bevy_render::ExtractSchedule, extract_live_image_requests);
+        render_app.insert_resource(LiveTextureSender(s));
+        render_app.init_resource::<LiveImageRequests>();
+
         // Add a system to get the GPU texture after assets are prepared
         render_app.add_systems(
             Render,
-            texture_getter_system.after(RenderSystems::PrepareAssets),
+            (texture_getter_system, send_live_textures).after(RenderSystems::PrepareAssets),
         );
     }
 }
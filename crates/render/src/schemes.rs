@@ -0,0 +1,74 @@
+//! Custom url schemes served to every document.
+//!
+//! ```ignore
+//! app.register_dioxus_scheme("pak", |request: Request| async move {
+//!     read_from_pak(request.url.path())
+//! });
+//! ```
+//! Then `<img src="pak://icons/sword.png">` loads through the handler.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use bevy_app::App;
+use bevy_ecs::prelude::*;
+use bevy_tasks::BoxedFuture;
+use blitz_traits::net::Request;
+use bytes::Bytes;
+
+pub type SchemeError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Serves the bytes of urls with a custom scheme. Run on the io task pool.
+pub trait DioxusSchemeHandler: Send + Sync + 'static {
+    fn fetch(&self, request: Request) -> BoxedFuture<'static, Result<Bytes, SchemeError>>;
+}
+
+impl<F, Fut> DioxusSchemeHandler for F
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Bytes, SchemeError>> + Send + 'static,
+{
+    fn fetch(&self, request: Request) -> BoxedFuture<'static, Result<Bytes, SchemeError>> {
+        Box::pin(self(request))
+    }
+}
+
+/// Registered scheme handlers, by scheme. Handlers registered after a document is created
+/// are not used by that document.
+#[derive(Resource, Clone, Default)]
+pub struct DioxusSchemes(pub(crate) Arc<HashMap<String, Arc<dyn DioxusSchemeHandler>>>);
+
+impl DioxusSchemes {
+    pub fn insert(&mut self, scheme: impl Into<String>, handler: impl DioxusSchemeHandler) {
+        Arc::make_mut(&mut self.0).insert(scheme.into().to_ascii_lowercase(), Arc::new(handler));
+    }
+
+    pub fn get(&self, scheme: &str) -> Option<&Arc<dyn DioxusSchemeHandler>> {
+        self.0.get(scheme)
+    }
+}
+
+pub trait DioxusSchemeAppExt {
+    /// Serve urls with `scheme` from `handler` in every document, taking precedence over
+    /// the built in schemes.
+    fn register_dioxus_scheme(
+        &mut self,
+        scheme: impl Into<String>,
+        handler: impl DioxusSchemeHandler,
+    ) -> &mut Self;
+}
+
+impl DioxusSchemeAppExt for App {
+    fn register_dioxus_scheme(
+        &mut self,
+        scheme: impl Into<String>,
+        handler: impl DioxusSchemeHandler,
+    ) -> &mut Self {
+        self.init_resource::<DioxusSchemes>();
+        self.world_mut()
+            .resource_mut::<DioxusSchemes>()
+            .insert(scheme, handler);
+        self
+    }
+}
@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use anyrender_vello::VelloScenePainter;
+use bevy_asset::AssetId;
 use bevy_dioxus_interop::DioxusMessage;
 use bevy_dioxus_tracing::{debug, error, warn};
 use bevy_ecs::prelude::*;
-use blitz_dom::Document;
+use bevy_image::Image;
+use bevy_math::Rect;
+use blitz_dom::node::RasterImageData;
+use blitz_dom::{Document, QualName, local_name, ns};
 use blitz_paint::paint_scene;
 use blitz_traits::events::UiEvent;
 use blitz_traits::shell::Viewport;
@@ -15,15 +19,20 @@ use dioxus_devtools::DevserverMsg;
 use dioxus_native::DioxusDocument;
 use vello::Scene;
 
+use crate::focus::{FocusNavigation, navigate_focus};
+use crate::live_image::{LiveImageSlot, apply_live_image_placeholders, collect_live_image_slots};
+use crate::panels::{DioxusPanels, DocumentBuilder};
 use crate::{COLOR_SCHEME, SCALE_FACTOR, does_catch_events};
 
-/// Extracts page coordinates from a UI event for hit-testing.
-fn extract_ui_event_coords(event: &UiEvent) -> (f32, f32) {
+/// Extracts page coordinates from a pointer UI event for hit-testing. Key and IME events
+/// have no position, so they aren't hit-tested.
+fn extract_ui_event_coords(event: &UiEvent) -> Option<(f32, f32)> {
     match event {
         UiEvent::PointerMove(e) | UiEvent::PointerDown(e) | UiEvent::PointerUp(e) => {
-            (e.coords.page_x, e.coords.page_y)
+            Some((e.coords.page_x, e.coords.page_y))
         }
-        _ => (0.0, 0.0),
+        UiEvent::Wheel(e) => Some((e.coords.page_x, e.coords.page_y)),
+        _ => None,
     }
 }
 
@@ -72,6 +81,17 @@ pub enum VdomCommand {
     Message(DioxusMessage),
     /// Update the viewport dimensions.
     Resize(u32, u32),
+    /// Fonts in the shared font context changed, re-layout text.
+    FontsChanged,
+    /// Re-fetch the stylesheets and images the document loaded from this href.
+    ReloadResource(String),
+    /// Move focus or click the focused element.
+    Navigate(FocusNavigation),
+    /// Keyboard focus moved to another document, clear the focused element.
+    Blur,
+    /// The placeholder vello swaps a live image's texture in for, or `None` once the
+    /// image is no longer available.
+    LiveImage(AssetId<Image>, Option<RasterImageData>),
     /// Stop the worker thread and drop the VDOM.
     Shutdown,
 }
@@ -83,9 +103,16 @@ pub enum VdomResult {
         scene: Scene,
         width: u32,
         height: u32,
+        /// Live bevy images the scene shows.
+        live_images: Vec<LiveImageSlot>,
     },
     /// Hit-test result: whether a DOM element with catch-events was under the pointer.
     HitTestResult { entity: Entity, caught: bool },
+    /// The document was rebuilt after a devserver full reload. Panels must be re-sent
+    /// on the new channel.
+    Rebuilt { panels: Sender<DioxusPanels> },
+    /// The focused text input moved, or focus moved on or off a text input.
+    TextInputFocus(Option<Rect>),
     /// Worker confirms shutdown.
     ShutdownAck,
 }
@@ -103,21 +130,36 @@ pub struct VdomWorker {
     pub waker_flag: Arc<AtomicBool>,
     /// Join handle for the worker thread.
     pub thread: Option<std::thread::JoinHandle<()>>,
+    /// Bounds of the document's focused text input, in document pixels.
+    pub focused_text_input: Option<Rect>,
 }
 
 /// Registry of all active VDOM worker threads.
 #[derive(Resource, Default, Debug)]
 pub struct VdomThreadRegistry {
     pub workers: HashMap<Entity, VdomWorker>,
+    /// Mirror quads and the source quad whose document they display.
+    pub mirrors: HashMap<Entity, Entity>,
+}
+
+impl VdomThreadRegistry {
+    /// Worker owning the document shown on `entity`, following mirrors back to their source.
+    pub fn worker_for(&self, entity: Entity) -> Option<&VdomWorker> {
+        let source = self.mirrors.get(&entity).copied().unwrap_or(entity);
+        self.workers.get(&source)
+    }
 }
 
 impl VdomWorker {
     /// Spawn a new OS thread that owns the VDOM and runs the poll and paint
     /// loop. The `messages_recv` channel receives messages from the document
-    /// proxy and from devtools, processed directly inside the worker.
+    /// proxy, processed directly inside the worker. Devserver messages arrive as
+    /// [`VdomCommand::Message`]. `builder` rebuilds the document on a devserver
+    /// full reload.
     pub fn spawn(
         entity: Entity,
         document: DioxusDocument,
+        builder: DocumentBuilder,
         messages_recv: Receiver<DioxusMessage>,
         cmd_rx: Receiver<VdomCommand>,
         result_tx: Sender<VdomResult>,
@@ -144,6 +186,12 @@ impl VdomWorker {
                 }));
 
                 let mut scene = Scene::new();
+                // Documents showing live images repaint every poll, since the images
+                // change without the document changing.
+                let mut has_live_images = false;
+                let mut live_placeholders = HashMap::new();
+                // Last focused text input reported to the main thread.
+                let mut last_text_input = None;
 
                 loop {
                     // Don't re-paint if nothing changed
@@ -151,23 +199,26 @@ impl VdomWorker {
 
                     // Process input events before polling.
                     while let Ok((ev_entity, ui_event)) = input_rx.try_recv() {
-                        let (x, y) = extract_ui_event_coords(&ui_event);
-                        let caught = document
-                            .inner
-                            .borrow()
-                            .hit(x, y)
-                            .map(|hit| does_catch_events(&document, hit.node_id))
-                            .unwrap_or(false);
+                        let caught = extract_ui_event_coords(&ui_event).map(|(x, y)| {
+                            document
+                                .inner
+                                .borrow()
+                                .hit(x, y)
+                                .map(|hit| does_catch_events(&document, hit.node_id))
+                                .unwrap_or(false)
+                        });
                         document.handle_ui_event(ui_event);
                         needs_paint = true;
-                        let _ = result_tx.try_send(VdomResult::HitTestResult {
-                            entity: ev_entity,
-                            caught,
-                        });
+                        if let Some(caught) = caught {
+                            let _ = result_tx.try_send(VdomResult::HitTestResult {
+                                entity: ev_entity,
+                                caught,
+                            });
+                        }
                     }
 
                     while let Ok(msg) = messages_recv.try_recv() {
-                        process_dioxus_message(&mut document, msg, &waker);
+                        process_dioxus_message(&mut document, msg, &waker, &builder, &result_tx);
                         needs_paint = true;
                     }
 
@@ -187,7 +238,33 @@ impl VdomWorker {
                                 needs_paint = true;
                             }
                             VdomCommand::Message(msg) => {
-                                process_dioxus_message(&mut document, msg, &waker);
+                                process_dioxus_message(
+                                    &mut document,
+                                    msg,
+                                    &waker,
+                                    &builder,
+                                    &result_tx,
+                                );
+                                needs_paint = true;
+                            }
+                            VdomCommand::FontsChanged => {
+                                document.inner.borrow_mut().invalidate_inline_contexts();
+                                needs_paint = true;
+                            }
+                            VdomCommand::ReloadResource(href) => {
+                                reload_resource(&document, &href, &builder);
+                                needs_paint = true;
+                            }
+                            VdomCommand::Navigate(navigation) => {
+                                navigate_focus(&mut document, navigation);
+                                needs_paint = true;
+                            }
+                            VdomCommand::Blur => {
+                                document.inner.borrow_mut().clear_focus();
+                                needs_paint = true;
+                            }
+                            VdomCommand::LiveImage(image, placeholder) => {
+                                set_live_placeholder(&mut live_placeholders, image, placeholder);
                                 needs_paint = true;
                             }
                             VdomCommand::Poll { animation_time } => {
@@ -199,6 +276,8 @@ impl VdomWorker {
                                     scene,
                                     &result_tx,
                                     needs_paint,
+                                    &mut has_live_images,
+                                    &live_placeholders,
                                 );
                                 scene = fresh;
                                 needs_paint = false;
@@ -220,6 +299,8 @@ impl VdomWorker {
                                 scene,
                                 &result_tx,
                                 needs_paint,
+                                &mut has_live_images,
+                                &live_placeholders,
                             );
                             scene = fresh;
                         }
@@ -233,33 +314,166 @@ impl VdomWorker {
                             // needs_paint = true;
                         }
                         Ok(VdomCommand::Message(msg)) => {
-                            process_dioxus_message(&mut document, msg, &waker);
+                            process_dioxus_message(
+                                &mut document,
+                                msg,
+                                &waker,
+                                &builder,
+                                &result_tx,
+                            );
                             // needs_paint = true;
                         }
+                        Ok(VdomCommand::FontsChanged) => {
+                            document.inner.borrow_mut().invalidate_inline_contexts();
+                        }
+                        Ok(VdomCommand::ReloadResource(href)) => {
+                            reload_resource(&document, &href, &builder);
+                        }
+                        Ok(VdomCommand::Navigate(navigation)) => {
+                            navigate_focus(&mut document, navigation);
+                        }
+                        Ok(VdomCommand::Blur) => {
+                            document.inner.borrow_mut().clear_focus();
+                        }
+                        Ok(VdomCommand::LiveImage(image, placeholder)) => {
+                            set_live_placeholder(&mut live_placeholders, image, placeholder);
+                        }
                         Err(_) => {
                             debug!("vdom-worker-{}: cmd channel closed", entity.index());
                             return;
                         }
                     }
+
+                    let text_input = focused_text_input(&document);
+                    if text_input != last_text_input {
+                        last_text_input = text_input;
+                        let _ = result_tx.try_send(VdomResult::TextInputFocus(text_input));
+                    }
                 }
             })
             .expect("failed to spawn vdom worker thread")
     }
 }
 
+fn set_live_placeholder(
+    placeholders: &mut HashMap<AssetId<Image>, RasterImageData>,
+    image: AssetId<Image>,
+    placeholder: Option<RasterImageData>,
+) {
+    match placeholder {
+        Some(placeholder) => placeholders.insert(image, placeholder),
+        None => placeholders.remove(&image),
+    };
+}
+
+/// Bounds of the focused element, if it's a text input.
+fn focused_text_input(document: &DioxusDocument) -> Option<Rect> {
+    let doc = document.inner.borrow();
+    let id = doc.get_focussed_node_id()?;
+    doc.get_node(id)?.element_data()?.text_input_data()?;
+    let rect = doc.get_client_bounding_rect(id)?;
+    Some(Rect::new(
+        rect.x as f32,
+        rect.y as f32,
+        (rect.x + rect.width) as f32,
+        (rect.y + rect.height) as f32,
+    ))
+}
+
+/// Added to reloaded image urls, making blitz fetch them again instead of using its cache.
+const IMAGE_RELOAD_FRAGMENT: &str = "#bevy-reload-";
+static IMAGE_RELOADS: AtomicU64 = AtomicU64::new(0);
+
+/// Re-fetch the stylesheets and images whose `href` or `src` is `href`, as written or
+/// resolved against the document's base url.
+fn reload_resource(document: &DioxusDocument, href: &str, builder: &DocumentBuilder) {
+    let base_url = builder.base_url();
+    let matches = |raw: &str| {
+        raw == href
+            || base_url
+                .as_ref()
+                .and_then(|base_url| base_url.join(raw).ok())
+                .is_some_and(|url| url.as_str() == href)
+    };
+
+    let mut doc = document.inner.borrow_mut();
+    let mut stylesheets = Vec::new();
+    let mut images = Vec::new();
+    for (node_id, node) in doc.tree().iter() {
+        let Some(element) = node.element_data() else {
+            continue;
+        };
+        if element.name.local == local_name!("link") {
+            if let Some(raw) = element.attr(local_name!("href"))
+                && matches(raw)
+            {
+                stylesheets.push(raw.to_string());
+            }
+        } else if element.name.local == local_name!("img")
+            && let Some(src) = element.attr(local_name!("src"))
+        {
+            let raw = src
+                .split_once(IMAGE_RELOAD_FRAGMENT)
+                .map_or(src, |(raw, _)| raw);
+            if matches(raw) {
+                images.push((node_id, raw.to_string()));
+            }
+        }
+    }
+
+    stylesheets.sort();
+    stylesheets.dedup();
+    for raw in stylesheets {
+        doc.reload_resource_by_href(&raw);
+    }
+    if images.is_empty() {
+        return;
+    }
+    // Blitz only fetches an image when its `src` changes, so the fragment is written straight
+    // into the dom, behind the VirtualDom's back. That's safe: the VirtualDom never reads
+    // attributes back, and the next render that writes `src` replaces the fragment with the
+    // value it holds anyway, which the cache strips the fragment from too.
+    let reload = IMAGE_RELOADS.fetch_add(1, Ordering::Relaxed);
+    let mut mutator = doc.mutate();
+    for (node_id, raw) in images {
+        mutator.set_attribute(
+            node_id,
+            QualName::new(None, ns!(), local_name!("src")),
+            &format!("{raw}{IMAGE_RELOAD_FRAGMENT}{reload}"),
+        );
+    }
+}
+
 /// Process a single dioxus message inside the worker.
-fn process_dioxus_message(doc: &mut DioxusDocument, msg: DioxusMessage, waker: &std::task::Waker) {
+fn process_dioxus_message(
+    doc: &mut DioxusDocument,
+    msg: DioxusMessage,
+    waker: &std::task::Waker,
+    builder: &DocumentBuilder,
+    result_tx: &Sender<VdomResult>,
+) {
     match msg {
         DioxusMessage::Devserver(devserver_msg) => match devserver_msg {
             DevserverMsg::HotReload(hotreload_message) => {
                 dioxus_devtools::apply_changes(&doc.vdom, &hotreload_message);
                 for asset_path in &hotreload_message.assets {
                     if let Some(url) = asset_path.to_str() {
-                        doc.inner.borrow_mut().reload_resource_by_href(url);
+                        reload_resource(doc, url, builder);
                     }
                 }
             }
-            DevserverMsg::FullReloadStart => {}
+            // Rebuild the document in place once the devserver's rebuild succeeded, keeping
+            // the quad, its texture and its panels. State kept in bevy survives, component
+            // state starts over. `FullReloadStart` may still be followed by a failed build.
+            // This runs the components already compiled into this process, so it only resets
+            // their state; new code needs the app restarted or a hot-patch.
+            DevserverMsg::FullReloadCommand => {
+                let viewport = doc.inner.borrow().viewport().clone();
+                let (rebuilt, panels) = builder.build();
+                rebuilt.inner.borrow_mut().set_viewport(viewport);
+                *doc = rebuilt;
+                let _ = result_tx.try_send(VdomResult::Rebuilt { panels });
+            }
             _ => {}
         },
         DioxusMessage::CreateHeadElement(el) => {
@@ -289,6 +503,8 @@ fn run_poll_and_paint(
     mut scene: Scene,
     result_tx: &Sender<VdomResult>,
     mut needs_paint: bool,
+    has_live_images: &mut bool,
+    live_placeholders: &HashMap<AssetId<Image>, RasterImageData>,
 ) -> Scene {
     loop {
         waker_flag.store(false, Ordering::SeqCst);
@@ -301,6 +517,9 @@ fn run_poll_and_paint(
         }
     }
 
+    if apply_live_image_placeholders(doc, live_placeholders) {
+        needs_paint = true;
+    }
     doc.inner.borrow_mut().resolve(animation_time);
 
     let (width, height) = {
@@ -314,7 +533,7 @@ fn run_poll_and_paint(
         return scene;
     }
 
-    if !needs_paint {
+    if !needs_paint && !*has_live_images {
         return scene;
     }
 
@@ -329,11 +548,15 @@ fn run_poll_and_paint(
         0,
     );
 
+    let live_images = collect_live_image_slots(doc);
+    *has_live_images = !live_images.is_empty();
+
     if result_tx
         .send(VdomResult::SceneReady {
             scene,
             width,
             height,
+            live_images,
         })
         .is_err()
     {
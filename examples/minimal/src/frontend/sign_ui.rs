@@ -1,5 +1,6 @@
 use bevy_color::Color;
 use bevy_ecs::{entity::Entity, query::With};
+use bevy_dioxus_render::owner::use_owner_entity;
 use bevy_pbr::{MeshMaterial3d, StandardMaterial};
 use dioxus::prelude::*;
 use dioxus_bevy_signals::{query::single::use_bevy_single, resource::use_bevy_resource};
@@ -13,6 +14,7 @@ const DISTANCE_INCREMENT: f32 = 1.0;
 
 #[component]
 pub fn sign_ui() -> Element {
+    let owner = use_owner_entity();
     let cube_distance = use_bevy_resource::<SignDistance, _, _>(|n| n, |err| err);
     let (_cube_entity, cube_color) =
         use_bevy_single::<(Entity, &mut MeshMaterial3d<StandardMaterial>), With<DynamicCube>>();
@@ -35,6 +37,10 @@ pub fn sign_ui() -> Element {
                 class: "sign-title",
                 "world space dom"
             }
+            span {
+                class: "sign-owner",
+                "rendered on {owner}"
+            }
             div {
                 class: "sign-control",
                 label { "Cube Distance:" }
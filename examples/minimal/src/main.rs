@@ -53,6 +53,8 @@ pub fn main() {
         .add_plugins(DioxusPlugin {
             bevy_info_refresh_fps: 30,
             main_window_ui: Some(app_ui),
+            root_ui: None,
+            devtools: cfg!(debug_assertions),
             dioxus_render_fps_cap: 60,
         })
         .add_plugins(BevyScenePlugin)